@@ -0,0 +1,797 @@
+//! CLI module for parsing runtime configuration from the command line.
+use chrono::Utc;
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
+use humantime;
+use s3_meta::config;
+use s3_meta::dates::{self, Formatter, Zone};
+use s3_meta::inventory::Location;
+use s3_meta::lifecycle;
+use s3_meta::metrics::{self, small_files};
+use s3_meta::options::Options;
+use s3_meta::source::Source;
+use s3_meta::types::MetaResult;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+/// Commands which can be run from the command line.
+pub enum Command {
+    /// Gathers metadata about a source and prints a report.
+    Scan(Box<Options>),
+    /// Compares two saved reports or listings.
+    Diff {
+        /// The older report or listing to compare from.
+        old: PathBuf,
+        /// The newer report or listing to compare to.
+        new: PathBuf,
+        /// The maximum number of entries in each list of changes.
+        limit: usize,
+    },
+    /// Prints the history and trends of a target.
+    History {
+        /// The database containing the history of scans.
+        database: PathBuf,
+        /// The bucket (or local directory) to print the history of.
+        bucket: String,
+        /// The prefix to print the history of.
+        prefix: String,
+        /// The formatter used when writing timestamps.
+        formatter: Formatter,
+    },
+}
+
+/// Main implementation.
+impl Command {
+    /// Parses a `Command` from the process arguments.
+    ///
+    /// Invalid arguments (and help/version flags) will terminate
+    /// the process after clap has printed the appropriate output.
+    pub fn from_env() -> MetaResult<Command> {
+        Command::from_args(std::env::args_os())
+    }
+
+    /// Parses a `Command` from an iterator of arguments.
+    ///
+    /// The first argument is expected to be the binary name, to
+    /// match the output of `std::env::args_os`.
+    pub fn from_args<I, T>(args: I) -> MetaResult<Command>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = build().get_matches_from(args);
+
+        // subcommands are parsed separately to the root options
+        if let Some(matches) = matches.subcommand_matches("diff") {
+            return Ok(Command::Diff {
+                old: matches.value_of("old").expect("old is required").into(),
+                new: matches.value_of("new").expect("new is required").into(),
+                limit: matches
+                    .value_of("limit")
+                    .unwrap_or("10")
+                    .parse()
+                    .map_err(|_| "Invalid limit provided")?,
+            });
+        }
+
+        if let Some(matches) = matches.subcommand_matches("history") {
+            let target = matches.value_of("target").expect("target is required");
+
+            // local directories are stored without a prefix
+            let (bucket, prefix) = if Source::is_local(target) {
+                (target.to_string(), None)
+            } else {
+                split_target(target)
+            };
+
+            return Ok(Command::History {
+                database: matches
+                    .value_of("database")
+                    .expect("database is required")
+                    .into(),
+                bucket,
+                prefix: prefix.unwrap_or_default(),
+                formatter: formatter(matches)?,
+            });
+        }
+
+        Ok(Command::Scan(Box::new(options(&matches)?)))
+    }
+}
+
+/// Constructs a `Formatter` from the timezone and format arguments.
+///
+/// This is shared by the scan and history commands, so that both write
+/// timestamps the same way.
+fn formatter(matches: &ArgMatches) -> MetaResult<Formatter> {
+    // parse the output timezone, defaulting to UTC
+    let zone = match matches.value_of("timezone") {
+        Some(zone) => {
+            dates::parse_zone(zone).ok_or_else(|| format!("Invalid timezone: {}", zone))?
+        }
+        None => Zone::Utc,
+    };
+
+    // construct the formatter with any custom format
+    let format = matches.value_of("time_format").map(|s| s.to_string());
+
+    Ok(Formatter::new(zone, format)?)
+}
+
+/// Converts a set of `ArgMatches` into `Options`.
+fn options(matches: &ArgMatches) -> MetaResult<Options> {
+    // grab the root path of the S3 location to use
+    let root_path = matches.value_of("target").expect("target is required");
+
+    // local directories are walked, rather than listed
+    if Source::is_local(root_path) {
+        // anything requiring S3 requests can't be used locally
+        let remote = [
+            "deep",
+            "from_listing",
+            "inventory",
+            "lifecycle_bucket",
+            "max_requests",
+            "max_time",
+            "owners",
+            "sample",
+            "sample_pages",
+            "tags",
+        ];
+
+        if let Some(arg) = remote.iter().find(|arg| matches.is_present(arg)) {
+            return Err(format!("Option {} cannot be used with local directories", arg).into());
+        }
+    }
+
+    // determine the bucket, prefix and source of objects
+    let (bucket, prefix, source) = if Source::is_local(root_path) {
+        (String::new(), None, Source::Directory(root_path.into()))
+    } else {
+        // split the path up to a (bucket, prefix)
+        let (bucket, prefix) = split_target(root_path);
+
+        // inventory reports and saved listings are read instead of listing
+        let source = match (
+            matches.value_of("inventory"),
+            matches.value_of("from_listing"),
+        ) {
+            (Some(manifest), _) => Source::Inventory(Location::parse(manifest)),
+            (_, Some(path)) => Source::Listing(path.into()),
+            (None, None) => Source::Bucket,
+        };
+
+        (bucket, prefix, source)
+    };
+
+    // parse the reference time, defaulting to the current time
+    let reference = match matches.value_of("reference") {
+        Some(time) => {
+            dates::parse(time).ok_or_else(|| format!("Invalid reference time: {}", time))?
+        }
+        None => Utc::now(),
+    };
+
+    // construct the formatter for timestamps
+    let formatter = formatter(matches)?;
+
+    // parse the timeline period, if any
+    let timeline = match matches.value_of("timeline") {
+        Some(period) => Some(period.parse()?),
+        None => None,
+    };
+
+    // load lifecycle rules from a file, or defer to the bucket
+    let lifecycle = match matches.value_of("lifecycle") {
+        Some(path) => Some(lifecycle::Source::Rules(lifecycle::parse(
+            &fs::read_to_string(path)?,
+        )?)),
+        None if matches.is_present("lifecycle_bucket") => Some(lifecycle::Source::Bucket),
+        None => None,
+    };
+
+    // parse the concurrency of deep inspection
+    let concurrency = matches
+        .value_of("concurrency")
+        .unwrap_or("16")
+        .parse()
+        .map_err(|_| "Invalid concurrency provided")?;
+
+    // parse the optional rate limit of deep inspection
+    let rate_limit = match matches.value_of("rate_limit") {
+        Some(limit) => Some(limit.parse().map_err(|_| "Invalid rate limit provided")?),
+        None => None,
+    };
+
+    // parse the sampling fraction, which must be within (0, 1]
+    let sample = match matches.value_of("sample") {
+        Some(fraction) => match fraction.parse::<f64>() {
+            Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Some(fraction),
+            _ => return Err("Invalid sample fraction provided".into()),
+        },
+        None => None,
+    };
+
+    // parse the number of pages to sample, which must be positive
+    let sample_pages = match matches.value_of("sample_pages") {
+        Some(pages) => match pages.parse::<u64>() {
+            Ok(pages) if pages > 0 => Some(pages),
+            _ => return Err("Invalid number of sample pages provided".into()),
+        },
+        None => None,
+    };
+
+    // parse the optional request budget
+    let max_requests = match matches.value_of("max_requests") {
+        Some(max) => Some(max.parse().map_err(|_| "Invalid request limit provided")?),
+        None => None,
+    };
+
+    // parse the optional time budget
+    let max_time = match matches.value_of("max_time") {
+        Some(time) => Some(humantime::parse_duration(time).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    // parse the small file threshold, if any
+    let small_files = match matches.value_of("small_files") {
+        Some(size) => Some(
+            small_files::parse_size(size)
+                .ok_or_else(|| format!("Invalid small file threshold: {}", size))?,
+        ),
+        None => None,
+    };
+
+    // load any custom groups from a config file
+    let groups = match matches.value_of("config") {
+        Some(path) => config::parse(&fs::read_to_string(path)?)?,
+        None => Vec::new(),
+    };
+
+    // parse the selected and skipped metric sections
+    let sections = |arg: &str| -> MetaResult<Vec<String>> {
+        let names = matches.values_of(arg).into_iter().flatten();
+        let sections: Vec<&str> = metrics::sections()
+            .into_iter()
+            .chain(groups.iter().map(|group| group.name.as_str()))
+            .collect();
+
+        names
+            .map(|name| {
+                if !sections.contains(&name) {
+                    return Err(format!(
+                        "Invalid metric section: {} (expected one of: {})",
+                        name,
+                        sections.join(", ")
+                    )
+                    .into());
+                }
+                Ok(name.to_string())
+            })
+            .collect()
+    };
+
+    let metrics = if matches.is_present("metrics") {
+        Some(sections("metrics")?)
+    } else {
+        None
+    };
+    let skip_metrics = sections("skip_metrics")?;
+
+    let options = Options {
+        bucket,
+        prefix,
+        reference,
+        formatter,
+        timeline,
+        owners: matches.is_present("owners"),
+        keys: matches.is_present("keys"),
+        hygiene: matches.is_present("hygiene"),
+        markers: matches.is_present("markers"),
+        partitions: matches.is_present("partitions"),
+        date_partitions: matches.is_present("date_partitions"),
+        collisions: matches.is_present("collisions"),
+        hot_spots: matches.is_present("hot_spots"),
+        small_files,
+        deep: matches.is_present("deep"),
+        tags: matches.is_present("tags"),
+        concurrency,
+        rate_limit,
+        lifecycle,
+        sample,
+        sample_pages,
+        max_requests,
+        max_time,
+        source,
+        dump_listing: matches.value_of("dump_listing").map(PathBuf::from),
+        history: matches.value_of("history").map(PathBuf::from),
+        metrics,
+        skip_metrics,
+        groups,
+    };
+
+    // history is keyed on the totals of the general section
+    if options.history.is_some() && !options.selects("general") {
+        return Err("Option history cannot be used without the general metrics".into());
+    }
+
+    Ok(options)
+}
+
+/// Splits an S3 target up into a bucket and optional prefix.
+fn split_target(target: &str) -> (String, Option<String>) {
+    let mut splitn = target.trim_start_matches("s3://").splitn(2, '/');
+
+    // bucket is required, prefix is optional after `/`
+    let bucket = splitn.next().unwrap().to_string();
+    let prefix = splitn.next().map(|s| s.to_string());
+
+    (bucket, prefix)
+}
+
+/// Constructs the command line interface definition.
+fn build<'a, 'b>() -> App<'a, 'b> {
+    App::new("")
+        .name(env!("CARGO_PKG_NAME"))
+        .about(env!("CARGO_PKG_DESCRIPTION"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .settings(&[
+            AppSettings::ArgRequiredElseHelp,
+            AppSettings::HidePossibleValuesInHelp,
+            AppSettings::SubcommandsNegateReqs,
+        ])
+        .args(&[
+            // concurrency: --concurrency <N>
+            Arg::with_name("concurrency")
+                .help("The number of concurrent requests to use when inspecting objects")
+                .long("concurrency")
+                .takes_value(true)
+                .requires("inspection"),
+            // collisions: --collisions
+            Arg::with_name("collisions")
+                .help("Detect keys which collide on case-insensitive or normalizing file systems")
+                .long("collisions"),
+            // config: --config <FILE>
+            Arg::with_name("config")
+                .help("A config file (TOML) defining custom metric groups")
+                .long("config")
+                .takes_value(true),
+            // date_partitions: --date-partitions
+            Arg::with_name("date_partitions")
+                .help("Detect date partitioned layouts and report any gaps")
+                .long("date-partitions"),
+            // deep: --deep
+            Arg::with_name("deep")
+                .help("Inspect object headers for content and encryption metrics")
+                .long("deep"),
+            // dump_listing: --dump-listing <FILE>
+            Arg::with_name("dump_listing")
+                .help("A file to save all objects seen during a scan to (as NDJSON)")
+                .long("dump-listing")
+                .takes_value(true),
+            // from_listing: --from-listing <FILE>
+            Arg::with_name("from_listing")
+                .help("A saved listing (JSON or NDJSON) to read objects from, rather than S3")
+                .long("from-listing")
+                .takes_value(true)
+                .conflicts_with_all(&[
+                    "deep",
+                    "inventory",
+                    "max_requests",
+                    "max_time",
+                    "sample",
+                    "sample_pages",
+                    "tags",
+                ]),
+            // history: --history <DATABASE>
+            Arg::with_name("history")
+                .help("A SQLite database to append the results of the scan to")
+                .long("history")
+                .takes_value(true)
+                .conflicts_with_all(&["max_requests", "max_time", "sample", "sample_pages"]),
+            // hot_spots: --hot-spots
+            Arg::with_name("hot_spots")
+                .help("Analyse the spread of keys across prefixes for request rate planning")
+                .long("hot-spots"),
+            // hygiene: --hygiene
+            Arg::with_name("hygiene")
+                .help("Audit key names for characters likely to break other tools")
+                .long("hygiene"),
+            // inventory: --inventory <MANIFEST>
+            Arg::with_name("inventory")
+                .help("An S3 Inventory manifest (local path or s3:// URL) to read objects from")
+                .long("inventory")
+                .takes_value(true)
+                .conflicts_with_all(&["max_requests", "max_time", "sample", "sample_pages"]),
+            // keys: --keys
+            Arg::with_name("keys")
+                .help("Report on the lengths and depths of keys")
+                .long("keys"),
+            // lifecycle: --lifecycle <FILE>
+            Arg::with_name("lifecycle")
+                .help("A lifecycle configuration file (JSON or XML) to simulate")
+                .long("lifecycle")
+                .takes_value(true)
+                .conflicts_with("lifecycle_bucket"),
+            // lifecycle_bucket: --lifecycle-bucket
+            Arg::with_name("lifecycle_bucket")
+                .help("Simulate the lifecycle configuration attached to the bucket")
+                .long("lifecycle-bucket"),
+            // markers: --markers
+            Arg::with_name("markers")
+                .help("Report on zero-byte objects, folder markers and empty folders")
+                .long("markers"),
+            // max_requests: --max-requests <N>
+            Arg::with_name("max_requests")
+                .help("A limit on the number of listing requests to make")
+                .long("max-requests")
+                .takes_value(true),
+            // max_time: --max-time <DURATION>
+            Arg::with_name("max_time")
+                .help("A limit on the duration of a scan (e.g. 5m)")
+                .long("max-time")
+                .takes_value(true),
+            // metrics: --metrics <SECTIONS>
+            Arg::with_name("metrics")
+                .help("A comma separated list of metric sections to report on")
+                .long("metrics")
+                .takes_value(true)
+                .use_delimiter(true)
+                .multiple(true)
+                .number_of_values(1),
+            // owners: --owners
+            Arg::with_name("owners")
+                .help("Fetch object owners and report usage per owner")
+                .long("owners"),
+            // partitions: --partitions
+            Arg::with_name("partitions")
+                .help("Detect Hive-style (key=value) partitions and report per partition")
+                .long("partitions"),
+            // rate_limit: --rate-limit <N>
+            Arg::with_name("rate_limit")
+                .help("A limit on requests per second to use when inspecting objects")
+                .long("rate-limit")
+                .takes_value(true)
+                .requires("inspection"),
+            // reference: --reference <TIME>
+            Arg::with_name("reference")
+                .help("A reference time to calculate object ages against")
+                .long("reference")
+                .takes_value(true),
+            // timeline: --timeline <PERIOD>
+            Arg::with_name("timeline")
+                .help("A period (day, week or month) to bucket an upload timeline into")
+                .long("timeline")
+                .takes_value(true)
+                .possible_values(&["day", "week", "month"]),
+            // time_format: --time-format <FORMAT>
+            Arg::with_name("time_format")
+                .help("A strftime format to use when writing timestamps")
+                .long("time-format")
+                .takes_value(true),
+            // timezone: --timezone <ZONE>
+            Arg::with_name("timezone")
+                .help("A timezone (UTC, local or an offset like +05:30) for writing timestamps")
+                .long("timezone")
+                .takes_value(true),
+            // sample: --sample <FRACTION>
+            Arg::with_name("sample")
+                .help("A fraction of prefixes to sample, to estimate totals")
+                .long("sample")
+                .takes_value(true),
+            // sample_pages: --sample-pages <N>
+            Arg::with_name("sample_pages")
+                .help("A number of random pages to sample, to estimate totals of flat prefixes")
+                .long("sample-pages")
+                .takes_value(true)
+                .conflicts_with("sample"),
+            // skip_metrics: --skip-metrics <SECTIONS>
+            Arg::with_name("skip_metrics")
+                .help("A comma separated list of metric sections to skip")
+                .long("skip-metrics")
+                .takes_value(true)
+                .use_delimiter(true)
+                .multiple(true)
+                .number_of_values(1),
+            // small_files: --small-files <SIZE>
+            Arg::with_name("small_files")
+                .help("A size (e.g. 128KB) to report files below, per prefix")
+                .long("small-files")
+                .takes_value(true),
+            // tags: --tags
+            Arg::with_name("tags")
+                .help("Inspect object tags for tagging metrics")
+                .long("tags"),
+            // target: +required
+            Arg::with_name("target")
+                .help("A bucket name (with an optional prefix) or local directory to gather metadata about (use -- before buckets named after a subcommand)")
+                .index(1)
+                .required(true),
+        ])
+        .group(
+            // inspection: deep | tags
+            ArgGroup::with_name("inspection")
+                .args(&["deep", "tags"])
+                .multiple(true),
+        )
+        .subcommand(
+            // diff: s3-meta diff <OLD> <NEW>
+            SubCommand::with_name("diff")
+                .about("Compares two saved reports or listings of a bucket")
+                .args(&[
+                    // limit: --limit <N>
+                    Arg::with_name("limit")
+                        .help("The maximum number of entries in each list of changes")
+                        .long("limit")
+                        .takes_value(true),
+                    // old: +required
+                    Arg::with_name("old")
+                        .help("The older report or listing to compare from")
+                        .index(1)
+                        .required(true),
+                    // new: +required
+                    Arg::with_name("new")
+                        .help("The newer report or listing to compare to")
+                        .index(2)
+                        .required(true),
+                ]),
+        )
+        .subcommand(
+            // history: s3-meta history <DATABASE> <TARGET>
+            SubCommand::with_name("history")
+                .about("Prints the history and trends of a bucket from past scans")
+                .args(&[
+                    // database: +required
+                    Arg::with_name("database")
+                        .help("The SQLite database containing the history of scans")
+                        .index(1)
+                        .required(true),
+                    // target: +required
+                    Arg::with_name("target")
+                        .help("A bucket name (with an optional prefix) or local directory")
+                        .index(2)
+                        .required(true),
+                    // time_format: --time-format <FORMAT>
+                    Arg::with_name("time_format")
+                        .help("A strftime format to use when writing timestamps")
+                        .long("time-format")
+                        .takes_value(true),
+                    // timezone: --timezone <ZONE>
+                    Arg::with_name("timezone")
+                        .help("A timezone (UTC, local or an offset like +05:30) for writing timestamps")
+                        .long("timezone")
+                        .takes_value(true),
+                ]),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Options};
+    use chrono::{TimeZone, Utc};
+    use s3_meta::inventory::Location;
+    use s3_meta::metrics::timeline::Period;
+    use s3_meta::source::Source;
+    use s3_meta::types::MetaResult;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn parse<I, T>(args: I) -> MetaResult<Options>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        match Command::from_args(args)? {
+            Command::Scan(options) => Ok(*options),
+            _ => Err("Unexpected command".into()),
+        }
+    }
+
+    #[test]
+    fn parsing_bucket_and_prefix() {
+        let options = parse(["s3-meta", "s3://my.bucket/my/prefix"]).unwrap();
+
+        assert_eq!(options.bucket, "my.bucket");
+        assert_eq!(options.prefix, Some("my/prefix".into()));
+
+        let options = parse(["s3-meta", "my.bucket"]).unwrap();
+
+        assert_eq!(options.bucket, "my.bucket");
+        assert_eq!(options.prefix, None);
+
+        let options = parse(["s3-meta", "diff.logs"]).unwrap();
+
+        assert_eq!(options.bucket, "diff.logs");
+        assert_eq!(options.prefix, None);
+
+        let options = parse(["s3-meta", "--", "diff"]).unwrap();
+
+        assert_eq!(options.bucket, "diff");
+        assert_eq!(options.prefix, None);
+    }
+
+    #[test]
+    fn parsing_listing_options() {
+        let options = parse([
+            "s3-meta",
+            "--from-listing",
+            "old.ndjson",
+            "--dump-listing",
+            "new.ndjson",
+            "my.bucket/logs/",
+        ])
+        .unwrap();
+
+        assert_eq!(options.source, Source::Listing("old.ndjson".into()));
+        assert_eq!(options.dump_listing, Some("new.ndjson".into()));
+        assert_eq!(options.prefix, Some("logs/".into()));
+    }
+
+    #[test]
+    fn parsing_local_directories() {
+        let options = parse(["s3-meta", "./my/dir"]).unwrap();
+
+        assert_eq!(options.source, Source::Directory("./my/dir".into()));
+        assert_eq!(options.prefix, None);
+        assert!(parse(["s3-meta", "--deep", "./my/dir"]).is_err());
+    }
+
+    #[test]
+    fn parsing_metric_sections() {
+        let options = parse(["s3-meta", "--metrics", "general,age", "my.bucket"]).unwrap();
+
+        assert_eq!(options.metrics, Some(vec!["general".into(), "age".into()]));
+        assert!(options.skip_metrics.is_empty());
+
+        let options = parse(["s3-meta", "--skip-metrics", "general", "my.bucket"]).unwrap();
+
+        assert_eq!(options.metrics, None);
+        assert_eq!(options.skip_metrics, vec!["general".to_string()]);
+
+        assert!(parse(["s3-meta", "--metrics", "unknown", "my.bucket"]).is_err());
+        assert!(parse([
+            "s3-meta",
+            "--skip-metrics",
+            "general",
+            "--history",
+            "runs.db",
+            "my.bucket"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn parsing_reference_time() {
+        let time = "2018-01-01T00:00:00+02:00";
+        let options = parse(["s3-meta", "--reference", time, "my.bucket"]).unwrap();
+
+        assert_eq!(
+            options.reference,
+            Utc.with_ymd_and_hms(2017, 12, 31, 22, 0, 0).unwrap()
+        );
+        assert!(parse(["s3-meta", "--reference", "tomorrow", "my.bucket"]).is_err());
+    }
+
+    #[test]
+    fn parsing_inspection_options() {
+        let options = parse([
+            "s3-meta",
+            "--tags",
+            "--concurrency",
+            "4",
+            "--rate-limit",
+            "100",
+            "my.bucket",
+        ])
+        .unwrap();
+
+        assert!(options.tags);
+        assert!(!options.deep);
+        assert_eq!(options.concurrency, 4);
+        assert_eq!(options.rate_limit, Some(100));
+    }
+
+    #[test]
+    fn parsing_sampling_options() {
+        let options = parse([
+            "s3-meta",
+            "--sample",
+            "0.25",
+            "--max-requests",
+            "100",
+            "--max-time",
+            "5m",
+            "my.bucket",
+        ])
+        .unwrap();
+
+        assert_eq!(options.sample, Some(0.25));
+        assert_eq!(options.max_requests, Some(100));
+        assert_eq!(options.max_time, Some(Duration::from_secs(300)));
+        assert!(parse(["s3-meta", "--sample", "1.5", "my.bucket"]).is_err());
+
+        let options = parse(["s3-meta", "--sample-pages", "50", "my.bucket"]).unwrap();
+
+        assert_eq!(options.sample_pages, Some(50));
+        assert!(parse(["s3-meta", "--sample-pages", "0", "my.bucket"]).is_err());
+    }
+
+    #[test]
+    fn parsing_inventory_options() {
+        let options = parse([
+            "s3-meta",
+            "--inventory",
+            "s3://my.inventory/manifest.json",
+            "my.bucket",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            options.source,
+            Source::Inventory(Location::Remote {
+                bucket: "my.inventory".into(),
+                key: "manifest.json".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn parsing_diff_commands() {
+        match Command::from_args(["s3-meta", "diff", "old.ndjson", "new.txt"]).unwrap() {
+            Command::Diff { old, new, limit } => {
+                assert_eq!(old, PathBuf::from("old.ndjson"));
+                assert_eq!(new, PathBuf::from("new.txt"));
+                assert_eq!(limit, 10);
+            }
+            _ => panic!("expected a diff command"),
+        }
+    }
+
+    #[test]
+    fn parsing_history_commands() {
+        match Command::from_args(["s3-meta", "history", "runs.db", "my.bucket/logs/"]).unwrap() {
+            Command::History {
+                database,
+                bucket,
+                prefix,
+                ..
+            } => {
+                assert_eq!(database, PathBuf::from("runs.db"));
+                assert_eq!(bucket, "my.bucket");
+                assert_eq!(prefix, "logs/");
+            }
+            _ => panic!("expected a history command"),
+        }
+
+        let args = [
+            "s3-meta",
+            "history",
+            "--timezone",
+            "+01:00",
+            "runs.db",
+            "my.bucket",
+        ];
+
+        match Command::from_args(args).unwrap() {
+            Command::History { formatter, .. } => {
+                let time = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
+                assert_eq!(formatter.format(&time), "2018-01-01T01:00:00.000+01:00");
+            }
+            _ => panic!("expected a history command"),
+        }
+
+        let options = parse(["s3-meta", "--history", "runs.db", "./my/dir"]).unwrap();
+
+        assert_eq!(options.history, Some("runs.db".into()));
+        assert_eq!(options.history_key(), ("./my/dir".into(), "".into()));
+    }
+
+    #[test]
+    fn parsing_timeline_period() {
+        let options = parse(["s3-meta", "--timeline", "week", "my.bucket"]).unwrap();
+
+        assert_eq!(options.timeline, Some(Period::Week));
+        assert_eq!(parse(["s3-meta", "my.bucket"]).unwrap().timeline, None);
+    }
+}
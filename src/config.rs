@@ -0,0 +1,130 @@
+//! Config module for loading custom metric definitions from a file.
+//!
+//! Configuration files are written in TOML, with each custom group defined
+//! as a table beneath `groups`. The name of the table is used as the name
+//! of the section in the output:
+//!
+//! ```toml
+//! [groups.tenants]
+//! group_by = "^tenants/(?P<tenant>[^/]+)/"
+//! ```
+use regex::Regex;
+use toml::{Table, Value};
+use types::MetaResult;
+
+/// Sections written outside of the metrics, which groups can't reuse.
+const RESERVED: [&str; 1] = ["sampling"];
+
+/// Group structure to represent a custom grouping of object keys.
+#[derive(Clone, Debug)]
+pub struct Group {
+    /// The name of the group, used as the section name.
+    pub name: String,
+    /// The pattern used to extract a group value from each key.
+    pub pattern: Regex,
+    /// An optional limit on the number of values to report.
+    pub limit: Option<usize>,
+}
+
+/// Parses all custom groups from the contents of a configuration file.
+///
+/// Group names must be lowercase (to match the built-in sections) and
+/// cannot replace any of the built-in sections.
+pub fn parse(contents: &str) -> MetaResult<Vec<Group>> {
+    let table: Table = contents.parse()?;
+    let mut groups = Vec::new();
+
+    for (key, value) in &table {
+        // only groups are currently supported
+        if key != "groups" {
+            return Err(format!("Unknown config key: {}", key).into());
+        }
+
+        let entries = value
+            .as_table()
+            .ok_or("Config key groups must be a table")?;
+
+        for (name, group) in entries {
+            groups.push(parse_group(name, group)?);
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Determines whether a name can be used as a section name in the output.
+pub fn is_section_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Parses a single custom group from its table.
+fn parse_group(name: &str, value: &Value) -> MetaResult<Group> {
+    // names are used as section names, so must be simple
+    if !is_section_name(name) {
+        return Err(format!("Invalid group name: {}", name).into());
+    }
+
+    // custom groups cannot clash with any built-in sections
+    if ::metrics::sections().contains(&name) || RESERVED.contains(&name) {
+        return Err(format!("Group name {} is already a built-in section", name).into());
+    }
+
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("Group {} must be a table", name))?;
+
+    let mut pattern = None;
+    let mut limit = None;
+
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("group_by", Value::String(regex)) => pattern = Some(Regex::new(regex)?),
+            ("limit", Value::Integer(max)) if *max > 0 => limit = Some(*max as usize),
+            _ => return Err(format!("Invalid key in group {}: {}", name, key).into()),
+        }
+    }
+
+    Ok(Group {
+        name: name.to_string(),
+        pattern: pattern.ok_or_else(|| format!("Group {} requires a group_by", name))?,
+        limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parsing_config_groups() {
+        let groups = super::parse(
+            r#"
+            [groups.tenants]
+            group_by = "^tenants/(?P<tenant>[^/]+)/"
+
+            [groups.years]
+            group_by = "/(\\d{4})/"
+            limit = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "tenants");
+        assert_eq!(groups[0].limit, None);
+        assert_eq!(groups[1].name, "years");
+        assert_eq!(groups[1].limit, Some(5));
+        assert!(groups[1].pattern.is_match("logs/2018/file.txt"));
+    }
+
+    #[test]
+    fn rejecting_invalid_groups() {
+        assert!(super::parse("[groups.general]\ngroup_by = \"^(.+)/\"").is_err());
+        assert!(super::parse("[groups.sampling]\ngroup_by = \"^(.+)/\"").is_err());
+        assert!(super::parse("[groups.Tenants]\ngroup_by = \"^(.+)/\"").is_err());
+        assert!(super::parse("[groups.tenants]\ngroup_by = \"^(.+/\"").is_err());
+        assert!(super::parse("[groups.tenants]\npattern = \"^(.+)/\"").is_err());
+        assert!(super::parse("[other]\nkey = 1").is_err());
+    }
+}
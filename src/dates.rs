@@ -0,0 +1,164 @@
+//! Date module to parse and format object timestamps.
+//!
+//! S3 returns modification times as ISO-8601 strings, but S3-compatible
+//! stores are not always consistent in their format or timezone. This
+//! module normalizes all of them into UTC timestamps for comparison.
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc,
+};
+use std::fmt::Display;
+
+/// Naive formats attempted (as UTC) when no timezone is provided.
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// Parses a timestamp string into a UTC `DateTime`.
+///
+/// RFC3339 and RFC2822 formats are both supported, alongside timestamps
+/// without any timezone information (which are assumed to be UTC).
+pub fn parse(input: &str) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc2822(input) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    NAIVE_FORMATS
+        .iter()
+        .filter_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+        .map(|time| time.and_utc())
+        .next()
+}
+
+/// Timezones available to use when formatting output.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Zone {
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+/// Formatter for timestamps written to the output.
+///
+/// By default timestamps are written as RFC3339 in UTC, which matches
+/// the representation returned by the S3 API itself.
+#[derive(Clone, Debug)]
+pub struct Formatter {
+    zone: Zone,
+    format: Option<String>,
+}
+
+/// Main implementation.
+impl Formatter {
+    /// Constructs a new `Formatter` from a zone and optional strftime format.
+    pub fn new(zone: Zone, format: Option<String>) -> Result<Formatter, String> {
+        if let Some(ref format) = format {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return Err(format!("Invalid time format: {}", format));
+            }
+        }
+        Ok(Formatter { zone, format })
+    }
+
+    /// Formats a UTC timestamp using the configured zone and format.
+    pub fn format(&self, time: &DateTime<Utc>) -> String {
+        match self.zone {
+            Zone::Utc => self.format_zoned(time),
+            Zone::Local => self.format_zoned(&time.with_timezone(&Local)),
+            Zone::Fixed(offset) => self.format_zoned(&time.with_timezone(&offset)),
+        }
+    }
+
+    /// Retrieves the calendar date of a UTC timestamp in the configured zone.
+    pub fn date(&self, time: &DateTime<Utc>) -> NaiveDate {
+        match self.zone {
+            Zone::Utc => time.date_naive(),
+            Zone::Local => time.with_timezone(&Local).date_naive(),
+            Zone::Fixed(offset) => time.with_timezone(&offset).date_naive(),
+        }
+    }
+
+    /// Formats a timestamp which has already been shifted to a timezone.
+    fn format_zoned<Tz>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        match self.format {
+            Some(ref format) => time.format(format).to_string(),
+            None => time.to_rfc3339_opts(SecondsFormat::Millis, true),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Formatter {
+    /// Creates a `Formatter` writing RFC3339 timestamps in UTC.
+    fn default() -> Formatter {
+        Formatter {
+            zone: Zone::Utc,
+            format: None,
+        }
+    }
+}
+
+/// Parses a timezone name or offset into a `Zone`.
+///
+/// Accepted values are `UTC`, `local`, or a fixed offset such as `+05:30`.
+pub fn parse_zone(input: &str) -> Option<Zone> {
+    match input.to_lowercase().as_str() {
+        "utc" | "z" => Some(Zone::Utc),
+        "local" => Some(Zone::Local),
+        offset => {
+            // reuse the RFC3339 parser to validate the offset
+            DateTime::parse_from_rfc3339(&format!("1970-01-01T00:00:00{}", offset))
+                .ok()
+                .map(|time| Zone::Fixed(*time.offset()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Formatter, Zone};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn parsing_timestamp_formats() {
+        let expected = Utc.with_ymd_and_hms(2016, 6, 11, 17, 36, 57).unwrap();
+
+        assert_eq!(super::parse("2016-06-11T17:36:57.000Z"), Some(expected));
+        assert_eq!(super::parse("2016-06-11T19:36:57+02:00"), Some(expected));
+        assert_eq!(
+            super::parse("Sat, 11 Jun 2016 17:36:57 GMT"),
+            Some(expected)
+        );
+        assert_eq!(super::parse("2016-06-11 17:36:57"), Some(expected));
+        assert_eq!(super::parse("yesterday"), None);
+    }
+
+    #[test]
+    fn parsing_timezones() {
+        assert_eq!(super::parse_zone("UTC"), Some(Zone::Utc));
+        assert_eq!(super::parse_zone("local"), Some(Zone::Local));
+        assert!(super::parse_zone("+05:30").is_some());
+        assert!(super::parse_zone("Mars/Olympus").is_none());
+    }
+
+    #[test]
+    fn formatting_timestamps() {
+        let time = Utc.with_ymd_and_hms(2016, 6, 11, 17, 36, 57).unwrap();
+        let zone = super::parse_zone("+02:00").unwrap();
+
+        let default = Formatter::default();
+        let shifted = Formatter::new(zone, Some("%Y-%m-%d %H:%M %z".into())).unwrap();
+
+        assert_eq!(default.format(&time), "2016-06-11T17:36:57.000Z");
+        assert_eq!(shifted.format(&time), "2016-06-11 19:36 +0200");
+        assert!(Formatter::new(Zone::Utc, Some("%Q".into())).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Deep module for fetching object metadata via per-object requests.
+//!
+//! Listings only contain a small subset of object metadata, so deeper
+//! inspection (such as headers and tags) requires a request per object.
+//! These requests are issued concurrently across a fixed number of threads,
+//! with an optional limit on the number of requests issued per second.
+use rusoto_s3::{
+    GetObjectTaggingRequest, HeadObjectOutput, HeadObjectRequest, Object, S3Client, Tag, S3,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use types::MetaResult;
+
+/// Fetcher structure to issue per-object requests for listed objects.
+pub struct Fetcher<'a> {
+    s3: &'a S3Client,
+    bucket: &'a str,
+    concurrency: usize,
+    interval: Option<Duration>,
+    next_slot: Mutex<Instant>,
+}
+
+/// Main implementation.
+impl<'a> Fetcher<'a> {
+    /// Constructs a new `Fetcher` for a bucket.
+    ///
+    /// The `rate_limit` is provided in requests per second; if not provided
+    /// requests are only bounded by the level of concurrency.
+    pub fn new(
+        s3: &'a S3Client,
+        bucket: &'a str,
+        concurrency: usize,
+        rate_limit: Option<u32>,
+    ) -> Fetcher<'a> {
+        Fetcher {
+            s3,
+            bucket,
+            concurrency: concurrency.max(1),
+            interval: rate_limit.map(|rate| Duration::from_secs(1) / rate.max(1)),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Fetches the headers for a page of objects.
+    pub fn heads(&self, objects: &[Object]) -> Vec<MetaResult<HeadObjectOutput>> {
+        self.fetch(objects, |object| self.head(object))
+    }
+
+    /// Fetches the tags for a page of objects.
+    pub fn tags(&self, objects: &[Object]) -> Vec<MetaResult<Vec<Tag>>> {
+        self.fetch(objects, |object| self.tagging(object))
+    }
+
+    /// Fetches a result for each of a page of objects.
+    ///
+    /// Results are returned in the same order as the provided objects, and
+    /// failures are returned per object rather than failing the whole page.
+    fn fetch<T, F>(&self, objects: &[Object], request: F) -> Vec<MetaResult<T>>
+    where
+        T: Send,
+        F: Fn(&Object) -> MetaResult<T> + Sync,
+    {
+        let cursor = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(objects.len()));
+
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(objects.len()) {
+                scope.spawn(|| loop {
+                    // claim the next object to inspect
+                    let idx = cursor.fetch_add(1, Ordering::SeqCst);
+                    let object = match objects.get(idx) {
+                        Some(object) => object,
+                        None => break,
+                    };
+
+                    // wait for a free request slot
+                    self.throttle();
+
+                    // execute the request and await the response (blocking)
+                    let result = request(object);
+
+                    results.lock().expect("lock poisoned").push((idx, result));
+                });
+            }
+        });
+
+        // restore the original ordering of the objects
+        let mut results = results.into_inner().expect("lock poisoned");
+        results.sort_by_key(|(idx, _)| *idx);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Issues a `HeadObject` request for a single object.
+    fn head(&self, object: &Object) -> MetaResult<HeadObjectOutput> {
+        let request = HeadObjectRequest {
+            bucket: self.bucket.to_string(),
+            key: object.key.clone().unwrap_or_default(),
+            ..HeadObjectRequest::default()
+        };
+        Ok(self.s3.head_object(request).sync()?)
+    }
+
+    /// Issues a `GetObjectTagging` request for a single object.
+    fn tagging(&self, object: &Object) -> MetaResult<Vec<Tag>> {
+        let request = GetObjectTaggingRequest {
+            bucket: self.bucket.to_string(),
+            key: object.key.clone().unwrap_or_default(),
+            ..GetObjectTaggingRequest::default()
+        };
+        Ok(self.s3.get_object_tagging(request).sync()?.tag_set)
+    }
+
+    /// Blocks until the next request slot is available.
+    fn throttle(&self) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        // reserve the next slot, and push the following slot back
+        let slot = {
+            let mut next_slot = self.next_slot.lock().expect("lock poisoned");
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + interval;
+            slot
+        };
+
+        // sleep until our reserved slot arrives
+        let now = Instant::now();
+        if slot > now {
+            thread::sleep(slot - now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fetcher;
+    use rusoto_core::Region;
+    use rusoto_s3::{Object, S3Client};
+    use std::time::{Duration, Instant};
+
+    fn objects(count: usize) -> Vec<Object> {
+        (0..count)
+            .map(|idx| Object {
+                key: Some(idx.to_string()),
+                ..Object::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fetching_in_object_order() {
+        let s3 = S3Client::new(Region::UsEast1);
+        let fetcher = Fetcher::new(&s3, "bucket", 4, None);
+        let objects = objects(32);
+
+        let results = fetcher.fetch(&objects, |object| match object.key.as_deref() {
+            Some("7") => Err("failed".into()),
+            key => Ok(key.unwrap_or_default().to_string()),
+        });
+
+        assert_eq!(results.len(), 32);
+        assert!(results[7].is_err());
+
+        for (idx, result) in results.iter().enumerate().filter(|(idx, _)| *idx != 7) {
+            assert_eq!(result.as_ref().unwrap(), &idx.to_string());
+        }
+    }
+
+    #[test]
+    fn throttling_requests() {
+        let s3 = S3Client::new(Region::UsEast1);
+        let fetcher = Fetcher::new(&s3, "bucket", 4, Some(100));
+        let start = Instant::now();
+
+        fetcher.fetch(&objects(6), |_| Ok(()));
+
+        // the first slot is free, the following five are 10ms apart
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,364 @@
+//! Diff module to compare two saved reports or listings of a bucket.
+//!
+//! Reports (the output of a scan) only contain aggregated values, so they
+//! can only be compared at that level. Listings contain every object, so
+//! comparing two listings also reports changes per prefix, per extension,
+//! and per object.
+use report::Report;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use types::MetaResult;
+
+/// The total files and bytes of a group of objects.
+type Totals = (u64, u64);
+
+/// The longest line read when checking for a report header.
+const HEADER_BYTES: u64 = 256;
+
+/// Snapshot of a bucket loaded from a report or listing.
+#[derive(Debug, Default)]
+struct Snapshot {
+    files: Option<u64>,
+    bytes: Option<u64>,
+    values: BTreeMap<String, f64>,
+    objects: Option<HashMap<String, u64>>,
+}
+
+/// Main implementation.
+impl Snapshot {
+    /// Loads a `Snapshot` from either a report or a listing.
+    fn load(path: &Path) -> MetaResult<Snapshot> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line = Vec::new();
+
+        // reports always begin with a section header, so only the first
+        // non-empty line is read (listings can be a single huge line)
+        while line.iter().all(u8::is_ascii_whitespace) {
+            line.clear();
+
+            if (&mut reader)
+                .take(HEADER_BYTES)
+                .read_until(b'\n', &mut line)?
+                == 0
+            {
+                break;
+            }
+        }
+
+        if is_report(String::from_utf8_lossy(&line).trim()) {
+            let mut contents = String::from_utf8_lossy(&line).into_owned();
+            reader.read_to_string(&mut contents)?;
+            return Ok(Snapshot::from_report(&contents));
+        }
+
+        let mut objects = HashMap::new();
+
+        ::listing::read(path, None, |page| {
+            for object in page {
+                let key = object.key.clone().unwrap_or_default();
+                let size = object.size.unwrap_or(0) as u64;
+
+                objects.insert(key, size);
+            }
+        })?;
+
+        Ok(Snapshot {
+            files: Some(objects.len() as u64),
+            bytes: Some(objects.values().sum()),
+            values: BTreeMap::new(),
+            objects: Some(objects),
+        })
+    }
+
+    /// Parses a `Snapshot` from the contents of a report.
+    ///
+    /// Only numeric values are kept, keyed by `<section>_<label>`.
+    fn from_report(contents: &str) -> Snapshot {
+        let mut section = "";
+        let mut values = BTreeMap::new();
+
+        for line in contents.lines().map(str::trim) {
+            // track the current section
+            if line.starts_with('[') && line.ends_with(']') {
+                section = &line[1..line.len() - 1];
+                continue;
+            }
+
+            // store any numeric values
+            if let Some((label, value)) = line.split_once('=') {
+                if let Ok(value) = value.parse::<f64>() {
+                    values.insert(format!("{}_{}", section, label), value);
+                }
+            }
+        }
+
+        Snapshot {
+            files: values.get("general_total_files").map(|v| *v as u64),
+            bytes: values.get("general_total_bytes").map(|v| *v as u64),
+            values,
+            objects: None,
+        }
+    }
+}
+
+/// Compares two saved reports or listings, reporting all changes.
+///
+/// Lists of changes are ranked by the size of the change, and are limited
+/// to the provided number of entries.
+pub fn run(old: &Path, new: &Path, limit: usize) -> MetaResult<Report> {
+    let old = Snapshot::load(old)?;
+    let new = Snapshot::load(new)?;
+
+    let mut report = Report::new();
+
+    // initial header!
+    report.head("totals");
+
+    // log out the totals, when known
+    report_change(&mut report, "files", old.files, new.files);
+    report_change(&mut report, "bytes", old.bytes, new.bytes);
+
+    // reports can only be compared on their values
+    if let (None, None) = (&old.objects, &new.objects) {
+        report_values(&mut report, &old.values, &new.values);
+    }
+
+    // listings can be compared in far more detail
+    if let (Some(old), Some(new)) = (&old.objects, &new.objects) {
+        report_objects(&mut report, old, new, limit);
+        report_groups(
+            &mut report,
+            ("prefixes", "prefix"),
+            old,
+            new,
+            limit,
+            prefix_of,
+        );
+        report_groups(
+            &mut report,
+            ("extensions", "extension"),
+            old,
+            new,
+            limit,
+            extension_of,
+        );
+    }
+
+    Ok(report)
+}
+
+/// Reports the changes between all numeric values of two reports.
+fn report_values(report: &mut Report, old: &BTreeMap<String, f64>, new: &BTreeMap<String, f64>) {
+    // next segment: values
+    report.head("values");
+
+    // only log the values found in both reports
+    for (label, before) in old {
+        if let Some(after) = new.get(label) {
+            if before != after {
+                report.pair(&format!("{}_before", label), before);
+                report.pair(&format!("{}_after", label), after);
+                report.pair(&format!("{}_delta", label), after - before);
+            }
+        }
+    }
+}
+
+/// Reports the objects added, removed and changed between two listings.
+fn report_objects(
+    report: &mut Report,
+    old: &HashMap<String, u64>,
+    new: &HashMap<String, u64>,
+    limit: usize,
+) {
+    let mut added: Vec<(&String, u64)> = Vec::new();
+    let mut removed: Vec<(&String, u64)> = Vec::new();
+    let mut changed: Vec<(&String, i128)> = Vec::new();
+
+    // find all added and changed objects
+    for (key, size) in new {
+        match old.get(key) {
+            None => added.push((key, *size)),
+            Some(before) if before != size => changed.push((key, delta(*before, *size))),
+            Some(_) => (),
+        }
+    }
+
+    // find all removed objects
+    for (key, size) in old {
+        if !new.contains_key(key) {
+            removed.push((key, *size));
+        }
+    }
+
+    // rank by the size of the change, then by key
+    added.sort_by_key(|(key, size)| (Reverse(*size), *key));
+    removed.sort_by_key(|(key, size)| (Reverse(*size), *key));
+    changed.sort_by_key(|(key, delta)| (Reverse(delta.abs()), *key));
+
+    // next segment: objects
+    report.head("objects");
+
+    report.pair("added_files", added.len());
+    report.pair("added_bytes", added.iter().map(|(_, s)| s).sum::<u64>());
+    report.pair("removed_files", removed.len());
+    report.pair("removed_bytes", removed.iter().map(|(_, s)| s).sum::<u64>());
+    report.pair("changed_files", changed.len());
+    report.pair(
+        "changed_bytes",
+        changed.iter().map(|(_, d)| d).sum::<i128>(),
+    );
+
+    // log out the largest changes of each kind
+    for (idx, (key, size)) in added.iter().take(limit).enumerate() {
+        report.pair(&format!("added_{}_name", idx + 1), key);
+        report.pair(&format!("added_{}_bytes", idx + 1), size);
+    }
+
+    for (idx, (key, size)) in removed.iter().take(limit).enumerate() {
+        report.pair(&format!("removed_{}_name", idx + 1), key);
+        report.pair(&format!("removed_{}_bytes", idx + 1), size);
+    }
+
+    for (idx, (key, delta)) in changed.iter().take(limit).enumerate() {
+        report.pair(&format!("changed_{}_name", idx + 1), key);
+        report.pair(&format!("changed_{}_bytes", idx + 1), delta);
+    }
+}
+
+/// Reports the changes in files and bytes of groups of objects.
+///
+/// Objects are grouped by the provided function, and only groups which
+/// changed are reported (ranked by the size of the change in bytes).
+fn report_groups<F>(
+    report: &mut Report,
+    (header, label): (&str, &str),
+    old: &HashMap<String, u64>,
+    new: &HashMap<String, u64>,
+    limit: usize,
+    group: F,
+) where
+    F: Fn(&str) -> Option<&str>,
+{
+    // sum up the (files, bytes) of each group
+    let totals = |objects: &HashMap<String, u64>| {
+        let mut totals: HashMap<String, Totals> = HashMap::new();
+
+        for (key, size) in objects {
+            if let Some(name) = group(key) {
+                let entry = totals.entry(name.to_string()).or_insert((0, 0));
+
+                entry.0 += 1;
+                entry.1 += size;
+            }
+        }
+
+        totals
+    };
+
+    let old = totals(old);
+    let new = totals(new);
+
+    // collect the before/after of every changed group
+    let mut changes: Vec<(&String, Totals, Totals)> = old
+        .keys()
+        .chain(new.keys().filter(|name| !old.contains_key(*name)))
+        .map(|name| {
+            let before = old.get(name).cloned().unwrap_or((0, 0));
+            let after = new.get(name).cloned().unwrap_or((0, 0));
+            (name, before, after)
+        })
+        .filter(|(_, before, after)| before != after)
+        .collect();
+
+    changes.sort_by_key(|(name, before, after)| (Reverse(delta(before.1, after.1).abs()), *name));
+
+    // next segment: groups
+    report.head(header);
+    report.pair(&format!("changed_{}", header), changes.len());
+
+    for (idx, (name, before, after)) in changes.iter().take(limit).enumerate() {
+        let label = format!("{}_{}", label, idx + 1);
+
+        report.pair(&format!("{}_name", label), name);
+        report.pair(&format!("{}_files_before", label), before.0);
+        report.pair(&format!("{}_files_after", label), after.0);
+        report.pair(&format!("{}_files_delta", label), delta(before.0, after.0));
+        report.pair(&format!("{}_bytes_before", label), before.1);
+        report.pair(&format!("{}_bytes_after", label), after.1);
+        report.pair(&format!("{}_bytes_delta", label), delta(before.1, after.1));
+    }
+}
+
+/// Reports the before, after and delta of a value, if known on both sides.
+fn report_change(report: &mut Report, label: &str, before: Option<u64>, after: Option<u64>) {
+    if let (Some(before), Some(after)) = (before, after) {
+        report.pair(&format!("{}_before", label), before);
+        report.pair(&format!("{}_after", label), after);
+        report.pair(&format!("{}_delta", label), delta(before, after));
+    }
+}
+
+/// Calculates the signed difference between two counters.
+fn delta(before: u64, after: u64) -> i128 {
+    i128::from(after) - i128::from(before)
+}
+
+/// Determines whether the first line of a file is a report section header.
+fn is_report(line: &str) -> bool {
+    line.strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .is_some_and(::config::is_section_name)
+}
+
+/// Retrieves the top level prefix of a key, or `<root>` for keys at the root.
+fn prefix_of(key: &str) -> Option<&str> {
+    match key.find('/') {
+        Some(idx) => Some(&key[..=idx]),
+        None => Some(::util::ROOT),
+    }
+}
+
+/// Retrieves the extension of a key, if it has one.
+fn extension_of(key: &str) -> Option<&str> {
+    Path::new(key).extension().and_then(|ext| ext.to_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snapshot;
+
+    #[test]
+    fn detecting_reports() {
+        assert!(super::is_report("[general]"));
+        assert!(super::is_report("[file_size]"));
+        assert!(super::is_report("[tenants2]"));
+        assert!(!super::is_report("[]"));
+        assert!(!super::is_report("[{\"Key\":\"file.txt\"}]"));
+        assert!(!super::is_report("{\"Key\":\"file.txt\"}"));
+    }
+
+    #[test]
+    fn parsing_report_snapshots() {
+        let snapshot = Snapshot::from_report(
+            "[general]\ntotal_time=7s\ntotal_files=12\ntotal_bytes=1024\n\n[age]\nolder_files=3\n",
+        );
+
+        assert_eq!(snapshot.files, Some(12));
+        assert_eq!(snapshot.bytes, Some(1024));
+        assert_eq!(snapshot.values.get("age_older_files"), Some(&3.0));
+        assert_eq!(snapshot.values.get("general_total_time"), None);
+    }
+
+    #[test]
+    fn grouping_object_keys() {
+        assert_eq!(super::prefix_of("logs/2018/file.txt"), Some("logs/"));
+        assert_eq!(super::prefix_of("file.txt"), Some("<root>"));
+        assert_eq!(super::extension_of("logs/file.txt"), Some("txt"));
+        assert_eq!(super::extension_of("logs/file"), None);
+        assert_eq!(super::delta(10, 4), -6);
+    }
+}
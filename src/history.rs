@@ -0,0 +1,302 @@
+//! History module to store the results of scans and report on trends.
+//!
+//! Every scan can be appended to a local SQLite database, keyed by bucket,
+//! prefix and time. Stored runs are then used to calculate the growth of a
+//! bucket over time, and to project its size into the future.
+use chrono::{DateTime, Utc};
+use dates::Formatter;
+use report::Report;
+use rusqlite::{params, Connection, OpenFlags};
+use std::path::Path;
+use types::MetaResult;
+
+/// The number of days to project the size of a bucket forward by.
+const PROJECTIONS: [u32; 2] = [30, 90];
+
+/// Schema of the history database, created on first use.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        bucket TEXT NOT NULL,
+        prefix TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        files INTEGER NOT NULL,
+        bytes INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS runs_target ON runs (bucket, prefix, timestamp);
+    CREATE TABLE IF NOT EXISTS results (
+        run_id INTEGER NOT NULL REFERENCES runs (id),
+        section TEXT NOT NULL,
+        label TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+";
+
+/// A stored run of a scan.
+#[derive(Debug, PartialEq)]
+struct Run {
+    timestamp: DateTime<Utc>,
+    files: u64,
+    bytes: u64,
+}
+
+/// Appends the results of a scan to the history database.
+///
+/// Totals are stored alongside the run to make trends cheap to calculate,
+/// with every other value of the report stored as-is in the results table.
+pub fn record(
+    path: &Path,
+    bucket: &str,
+    prefix: &str,
+    timestamp: DateTime<Utc>,
+    report: &Report,
+) -> MetaResult<()> {
+    let mut conn = create(path)?;
+    let tx = conn.transaction()?;
+
+    // pull the totals from the general section
+    let total = |label: &str| {
+        report
+            .get("general", label)
+            .and_then(|val| val.parse::<i64>().ok())
+            .unwrap_or(0)
+    };
+
+    tx.execute(
+        "INSERT INTO runs (bucket, prefix, timestamp, files, bytes) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            bucket,
+            prefix,
+            timestamp.to_rfc3339(),
+            total("total_files"),
+            total("total_bytes")
+        ],
+    )?;
+
+    let run_id = tx.last_insert_rowid();
+
+    // store every value of every section
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO results (run_id, section, label, value) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+
+        for section in report.sections() {
+            for (label, value) in &section.pairs {
+                insert.execute(params![run_id, section.name, label, value])?;
+            }
+        }
+    }
+
+    Ok(tx.commit()?)
+}
+
+/// Reports the history and trends of a bucket and prefix.
+pub fn run(path: &Path, bucket: &str, prefix: &str, formatter: &Formatter) -> MetaResult<Report> {
+    let runs = load(&open(path)?, bucket, prefix)?;
+    let mut report = Report::new();
+
+    // initial header!
+    report.head("history");
+    report.pair("total_runs", runs.len());
+
+    // nothing else to report without any runs
+    let (first, last) = match (runs.first(), runs.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(report),
+    };
+
+    report.pair("first_run_time", formatter.format(&first.timestamp));
+    report.pair("last_run_time", formatter.format(&last.timestamp));
+    report.pair("latest_files", last.files);
+    report.pair("latest_storage", ::util::convert_bytes(last.bytes));
+    report.pair("latest_bytes", last.bytes);
+
+    // trends require runs across a period of time
+    if let Some((files_per_day, bytes_per_day)) = trend(&runs) {
+        // next segment: trend
+        report.head("trend");
+
+        report.pair("files_per_day", files_per_day.round());
+        report.pair("bytes_per_day", bytes_per_day.round());
+
+        // project forward from the latest run
+        for days in &PROJECTIONS {
+            let project = |latest: u64, rate: f64| {
+                (latest as f64 + rate * f64::from(*days)).max(0.0).round() as u64
+            };
+
+            let files = project(last.files, files_per_day);
+            let bytes = project(last.bytes, bytes_per_day);
+
+            report.pair(&format!("projected_{}d_files", days), files);
+            report.pair(
+                &format!("projected_{}d_storage", days),
+                ::util::convert_bytes(bytes),
+            );
+            report.pair(&format!("projected_{}d_bytes", days), bytes);
+        }
+    }
+
+    // next segment: runs
+    report.head("runs");
+
+    for (idx, run) in runs.iter().enumerate() {
+        let label = format!("run_{}", idx + 1);
+
+        report.pair(&format!("{}_time", label), formatter.format(&run.timestamp));
+        report.pair(&format!("{}_files", label), run.files);
+        report.pair(&format!("{}_bytes", label), run.bytes);
+    }
+
+    Ok(report)
+}
+
+/// Opens the database at a path for writing, creating it if necessary.
+fn create(path: &Path) -> MetaResult<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// Opens an existing database at a path for reading.
+///
+/// Reading never creates a database, so a mistyped path is an error
+/// rather than an empty history.
+fn open(path: &Path) -> MetaResult<Connection> {
+    if !path.is_file() {
+        return Err(format!("History database not found: {}", path.display()).into());
+    }
+
+    Ok(Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?)
+}
+
+/// Loads all runs of a bucket and prefix, ordered by time.
+fn load(conn: &Connection, bucket: &str, prefix: &str) -> MetaResult<Vec<Run>> {
+    let mut query = conn.prepare(
+        "SELECT timestamp, files, bytes FROM runs
+         WHERE bucket = ?1 AND prefix = ?2
+         ORDER BY timestamp",
+    )?;
+
+    let rows = query.query_map(params![bucket, prefix], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+
+    let mut runs = Vec::new();
+
+    for row in rows {
+        let (timestamp, files, bytes) = row?;
+
+        runs.push(Run {
+            timestamp: ::dates::parse(&timestamp)
+                .ok_or_else(|| format!("Invalid timestamp in history: {}", timestamp))?,
+            files: files as u64,
+            bytes: bytes as u64,
+        });
+    }
+
+    Ok(runs)
+}
+
+/// Calculates the daily growth of files and bytes across runs.
+///
+/// Growth is the slope of a least squares fit over all runs, so a single
+/// unusual run has less impact than a simple comparison of two runs would.
+fn trend(runs: &[Run]) -> Option<(f64, f64)> {
+    let first = runs.first()?.timestamp;
+
+    // convert the times of all runs to days since the first run
+    let days: Vec<f64> = runs
+        .iter()
+        .map(|run| (run.timestamp - first).num_seconds() as f64 / 86_400.0)
+        .collect();
+
+    let count = days.len() as f64;
+    let mean = days.iter().sum::<f64>() / count;
+    let variance: f64 = days.iter().map(|day| (day - mean).powi(2)).sum();
+
+    // runs must be spread over time to have a trend
+    if variance == 0.0 {
+        return None;
+    }
+
+    let slope = |values: &dyn Fn(&Run) -> u64| {
+        let values: Vec<f64> = runs.iter().map(|run| values(run) as f64).collect();
+        let average = values.iter().sum::<f64>() / count;
+
+        days.iter()
+            .zip(values)
+            .map(|(day, value)| (day - mean) * (value - average))
+            .sum::<f64>()
+            / variance
+    };
+
+    Some((slope(&|run| run.files), slope(&|run| run.bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Run;
+    use chrono::{TimeZone, Utc};
+    use report::Report;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn calculating_trends() {
+        let runs: Vec<Run> = (0..3)
+            .map(|day| Run {
+                timestamp: Utc.with_ymd_and_hms(2018, 1, 1 + day, 0, 0, 0).unwrap(),
+                files: 10 + u64::from(day) * 5,
+                bytes: 1000 - u64::from(day) * 100,
+            })
+            .collect();
+
+        let (files, bytes) = super::trend(&runs).unwrap();
+
+        assert!((files - 5.0).abs() < 1e-6);
+        assert!((bytes + 100.0).abs() < 1e-6);
+        assert_eq!(super::trend(&runs[..1]), None);
+    }
+
+    #[test]
+    fn recording_and_loading_runs() {
+        let path = env::temp_dir().join(format!("s3-meta-history-{}.db", std::process::id()));
+        let time = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
+
+        // reading never creates a missing database
+        assert!(super::open(&path).is_err());
+        assert!(!path.exists());
+
+        let mut report = Report::new();
+        report.head("general");
+        report.pair("total_files", 3);
+        report.pair("total_bytes", 512);
+
+        super::record(&path, "my.bucket", "logs/", time, &report).unwrap();
+
+        let conn = super::open(&path).unwrap();
+        let runs = super::load(&conn, "my.bucket", "logs/").unwrap();
+        let other = super::load(&conn, "my.bucket", "").unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            runs,
+            vec![Run {
+                timestamp: time,
+                files: 3,
+                bytes: 512,
+            }]
+        );
+        assert!(other.is_empty());
+    }
+}
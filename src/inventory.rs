@@ -0,0 +1,725 @@
+//! Inventory module to read S3 Inventory reports as a source of objects.
+//!
+//! Inventory reports consist of a `manifest.json` file describing a set of
+//! data files, which contain a row per object in the source bucket. Reports
+//! can be read from either a local directory, or directly from S3, in the
+//! CSV format (or the Parquet and ORC formats, with the `columnar` feature).
+#[cfg(feature = "columnar")]
+use arrow::array::RecordBatch;
+#[cfg(feature = "columnar")]
+use arrow::error::ArrowError;
+#[cfg(feature = "columnar")]
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use csv::ReaderBuilder;
+use flate2::read::GzDecoder;
+use futures::stream::{Stream, Wait};
+#[cfg(feature = "columnar")]
+use orc_rust::arrow_reader::ArrowReaderBuilder;
+#[cfg(feature = "columnar")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rusoto_s3::{GetObjectRequest, Object, S3Client, StreamingBody, S3};
+use serde_json::{self, Value};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "columnar")]
+use std::{env, fs, process};
+use types::MetaResult;
+
+/// The number of rows to pass through in each page.
+const PAGE_SIZE: usize = 1000;
+
+/// Locations an inventory manifest can be read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Location {
+    /// A manifest stored on the local filesystem.
+    Local(PathBuf),
+    /// A manifest stored in an S3 bucket.
+    Remote { bucket: String, key: String },
+}
+
+/// Main implementation.
+impl Location {
+    /// Parses a `Location` from either a local path or an `s3://` URL.
+    pub fn parse(input: &str) -> Location {
+        if !input.starts_with("s3://") {
+            return Location::Local(PathBuf::from(input));
+        }
+
+        let mut splitn = input.trim_start_matches("s3://").splitn(2, '/');
+
+        Location::Remote {
+            bucket: splitn.next().unwrap().to_string(),
+            key: splitn.next().unwrap_or("").to_string(),
+        }
+    }
+}
+
+/// Representation of an inventory manifest.
+#[derive(Debug)]
+pub struct Manifest {
+    /// The name of the bucket the inventory describes.
+    pub source_bucket: String,
+    /// The name of the bucket the inventory is stored in.
+    pub destination_bucket: String,
+    /// The format of the inventory data files.
+    pub file_format: String,
+    /// The column names of the inventory data files (only used for CSV).
+    pub file_schema: Vec<String>,
+    /// The keys of all inventory data files.
+    pub files: Vec<String>,
+}
+
+/// Main implementation.
+impl Manifest {
+    /// Parses a `Manifest` from the JSON representation.
+    pub fn parse(input: &str) -> MetaResult<Manifest> {
+        let value: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+        let field = |name: &str| value[name].as_str().unwrap_or("").to_string();
+
+        // destinations are provided as ARNs
+        let destination = field("destinationBucket");
+        let destination = destination.trim_start_matches("arn:aws:s3:::");
+
+        Ok(Manifest {
+            source_bucket: field("sourceBucket"),
+            destination_bucket: destination.to_string(),
+            file_format: field("fileFormat"),
+            file_schema: field("fileSchema")
+                .split(',')
+                .map(|column| column.trim().to_string())
+                .filter(|column| !column.is_empty())
+                .collect(),
+            files: value["files"]
+                .as_array()
+                .ok_or("Inventory manifest has no files")?
+                .iter()
+                .filter_map(|file| file["key"].as_str())
+                .map(|key| key.to_string())
+                .collect(),
+        })
+    }
+}
+
+/// Inventory-only details of an object, which are not included in listings.
+///
+/// Storage classes are provided on the `Object` itself, as listings
+/// include them too. Values are `None` when a column is not included
+/// in the report (or is empty for the object).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    /// The encryption status of the object, e.g. `SSE-KMS`.
+    pub encryption_status: Option<String>,
+    /// The replication status of the object, e.g. `COMPLETED`.
+    pub replication_status: Option<String>,
+    /// The object lock retention mode, e.g. `GOVERNANCE`.
+    pub object_lock_mode: Option<String>,
+    /// The date the object lock retention expires.
+    pub object_lock_retain_until: Option<String>,
+    /// The object lock legal hold status, e.g. `ON`.
+    pub object_lock_legal_hold: Option<String>,
+    /// The Intelligent-Tiering access tier, e.g. `ARCHIVE`.
+    pub access_tier: Option<String>,
+}
+
+/// Formats inventory data files can be written in.
+enum Format {
+    /// Gzipped CSV files, with URL encoded keys.
+    Csv,
+    /// Apache Parquet files.
+    #[cfg(feature = "columnar")]
+    Parquet,
+    /// Apache ORC files.
+    #[cfg(feature = "columnar")]
+    Orc,
+}
+
+/// Main implementation.
+impl Format {
+    /// Parses a `Format` from the `fileFormat` of a manifest.
+    fn parse(input: &str) -> MetaResult<Format> {
+        match input.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            #[cfg(feature = "columnar")]
+            "parquet" => Ok(Format::Parquet),
+            #[cfg(feature = "columnar")]
+            "orc" => Ok(Format::Orc),
+            #[cfg(not(feature = "columnar"))]
+            "parquet" | "orc" => {
+                Err(format!("Inventory format requires the columnar feature: {}", input).into())
+            }
+            _ => Err(format!("Unsupported inventory format: {}", input).into()),
+        }
+    }
+}
+
+/// Record batches decoded from a columnar data file.
+#[cfg(feature = "columnar")]
+type Batches = Box<dyn Iterator<Item = Result<RecordBatch, ArrowError>>>;
+
+/// Pager structure to filter rows and pass them through in pages.
+struct Pager<'p, F> {
+    prefix: Option<&'p String>,
+    objects: Vec<Object>,
+    records: Vec<Record>,
+    handler: F,
+}
+
+/// Main implementation.
+impl<'p, F> Pager<'p, F>
+where
+    F: FnMut(&[Object], &[Record]),
+{
+    /// Adds a row to the current page, flushing the page when full.
+    fn push(&mut self, (object, record): (Object, Record)) {
+        // filter out anything outside of the prefix
+        if let Some(prefix) = self.prefix {
+            if !object
+                .key
+                .as_ref()
+                .is_some_and(|key| key.starts_with(prefix.as_str()))
+            {
+                return;
+            }
+        }
+
+        self.objects.push(object);
+        self.records.push(record);
+
+        if self.objects.len() == PAGE_SIZE {
+            self.flush();
+        }
+    }
+
+    /// Passes the current page through to the handler.
+    fn flush(&mut self) {
+        if !self.objects.is_empty() {
+            (self.handler)(&self.objects, &self.records);
+            self.objects.clear();
+            self.records.clear();
+        }
+    }
+}
+
+/// Reads all objects in an inventory report, passing them through in pages.
+///
+/// Only the latest version of each object is included (to match a listing),
+/// and objects can optionally be filtered to those beneath a prefix. The
+/// inventory must describe the provided bucket.
+pub fn read<F>(
+    s3: &S3Client,
+    location: &Location,
+    bucket: &str,
+    prefix: Option<&String>,
+    handler: F,
+) -> MetaResult<()>
+where
+    F: FnMut(&[Object], &[Record]),
+{
+    // load and parse the manifest
+    let mut manifest = String::new();
+    open(s3, location)?.read_to_string(&mut manifest)?;
+    let manifest = Manifest::parse(&manifest)?;
+
+    // make sure the inventory describes the target bucket
+    if !manifest.source_bucket.is_empty() && manifest.source_bucket != bucket {
+        return Err(format!(
+            "Inventory describes another bucket: {}",
+            manifest.source_bucket
+        )
+        .into());
+    }
+
+    // determine the format of the data files
+    let format = Format::parse(&manifest.file_format)?;
+
+    // pager used to buffer the current page
+    let mut pager = Pager {
+        prefix,
+        objects: Vec::with_capacity(PAGE_SIZE),
+        records: Vec::with_capacity(PAGE_SIZE),
+        handler,
+    };
+
+    for file in &manifest.files {
+        // resolve the location of the data file
+        let data = resolve(location, &manifest, file);
+
+        match format {
+            Format::Csv => read_csv(s3, &data, &manifest.file_schema, &mut pager)?,
+            #[cfg(feature = "columnar")]
+            _ => read_columnar(s3, &data, &format, &mut pager)?,
+        }
+    }
+
+    // flush the final page
+    pager.flush();
+
+    Ok(())
+}
+
+/// Reads the rows of a gzipped CSV data file.
+///
+/// CSV data files contain no headers, so the schema of the manifest
+/// is used to determine the name of each column.
+fn read_csv<F>(
+    s3: &S3Client,
+    location: &Location,
+    schema: &[String],
+    pager: &mut Pager<F>,
+) -> MetaResult<()>
+where
+    F: FnMut(&[Object], &[Record]),
+{
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(GzDecoder::new(open(s3, location)?));
+
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let values: Vec<&str> = record.iter().collect();
+
+        if let Some(row) = parse_row(schema, &values, true) {
+            pager.push(row);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the rows of a Parquet or ORC data file.
+///
+/// Columnar data files embed their own schema, so column names are taken
+/// from each batch. Remote files are spooled to a temporary file (rather
+/// than memory), as both formats require random access to their footers.
+#[cfg(feature = "columnar")]
+fn read_columnar<F>(
+    s3: &S3Client,
+    location: &Location,
+    format: &Format,
+    pager: &mut Pager<F>,
+) -> MetaResult<()>
+where
+    F: FnMut(&[Object], &[Record]),
+{
+    // remote files are removed once the spool is dropped
+    let mut spool = None;
+    let path = match location {
+        Location::Local(path) => path.clone(),
+        Location::Remote { key, .. } => {
+            let name = key.rsplit('/').next().unwrap_or_default();
+            let path = env::temp_dir().join(format!("s3-meta-{}-{}", process::id(), name));
+            let spool = spool.insert(Spool(path));
+
+            io::copy(&mut open(s3, location)?, &mut File::create(&spool.0)?)?;
+            spool.0.clone()
+        }
+    };
+
+    let batches = batches(format, File::open(&path)?)?;
+
+    let options = FormatOptions::default();
+
+    for batch in batches {
+        let batch = batch.map_err(|e| e.to_string())?;
+        let fields = batch.schema();
+
+        // format every column we're able to as strings, skipping the rest
+        let (schema, formatters): (Vec<String>, Vec<ArrayFormatter>) = fields
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .filter_map(|(field, column)| {
+                ArrayFormatter::try_new(column.as_ref(), &options)
+                    .ok()
+                    .map(|formatter| (field.name().to_string(), formatter))
+            })
+            .unzip();
+
+        for idx in 0..batch.num_rows() {
+            let values: Vec<String> = formatters
+                .iter()
+                .map(|formatter| formatter.value(idx).to_string())
+                .collect();
+            let values: Vec<&str> = values.iter().map(|value| value.as_str()).collect();
+
+            if let Some(row) = parse_row(&schema, &values, false) {
+                pager.push(row);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Temporary file which is removed once dropped.
+#[cfg(feature = "columnar")]
+struct Spool(PathBuf);
+
+/// Drop implementation.
+#[cfg(feature = "columnar")]
+impl Drop for Spool {
+    /// Removes the temporary file, ignoring any failures.
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Creates an iterator of record batches over a columnar data file.
+#[cfg(feature = "columnar")]
+fn batches<R>(format: &Format, reader: R) -> MetaResult<Batches>
+where
+    R: parquet::file::reader::ChunkReader + orc_rust::reader::ChunkReader + 'static,
+{
+    match format {
+        Format::Parquet => {
+            let reader = ParquetRecordBatchReaderBuilder::try_new(reader)
+                .and_then(|builder| builder.build())
+                .map_err(|e| e.to_string())?;
+            Ok(Box::new(reader))
+        }
+        Format::Orc => {
+            let reader = ArrowReaderBuilder::try_new(reader).map_err(|e| e.to_string())?;
+            Ok(Box::new(reader.build()))
+        }
+        Format::Csv => Err("CSV files are not columnar".into()),
+    }
+}
+
+/// Parses a row of an inventory data file into an `Object` and `Record`.
+///
+/// Columns are matched by name regardless of case and underscores, as CSV
+/// schemas use `LastModifiedDate` whereas columnar formats use names like
+/// `last_modified_date`. Rows describing delete markers or non-current
+/// versions are skipped.
+fn parse_row(schema: &[String], values: &[&str], encoded: bool) -> Option<(Object, Record)> {
+    // retrieves a non-empty column value by name
+    let column = |name: &str| {
+        schema
+            .iter()
+            .position(|column| matches_column(column, name))
+            .and_then(|idx| values.get(idx).cloned())
+            .filter(|value| !value.is_empty())
+    };
+    let owned = |name: &str| column(name).map(|value| value.to_string());
+
+    // skip anything which wouldn't appear in a listing
+    if column("IsLatest") == Some("false") || column("IsDeleteMarker") == Some("true") {
+        return None;
+    }
+
+    // only CSV files have encoded keys
+    let key = column("Key")?;
+    let key = if encoded {
+        decode_key(key)
+    } else {
+        key.to_string()
+    };
+
+    let object = Object {
+        key: Some(key),
+        size: Some(
+            column("Size")
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(0),
+        ),
+        last_modified: owned("LastModifiedDate"),
+        e_tag: owned("ETag"),
+        storage_class: owned("StorageClass"),
+        owner: None,
+    };
+
+    let record = Record {
+        encryption_status: owned("EncryptionStatus"),
+        replication_status: owned("ReplicationStatus"),
+        object_lock_mode: owned("ObjectLockMode"),
+        object_lock_retain_until: owned("ObjectLockRetainUntilDate"),
+        object_lock_legal_hold: owned("ObjectLockLegalHoldStatus"),
+        access_tier: owned("IntelligentTieringAccessTier"),
+    };
+
+    Some((object, record))
+}
+
+/// Determines whether a column name matches, ignoring case and underscores.
+fn matches_column(column: &str, name: &str) -> bool {
+    column
+        .chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .eq(name.chars().map(|c| c.to_ascii_lowercase()))
+}
+
+/// Decodes a URL encoded object key from an inventory report.
+fn decode_key(key: &str) -> String {
+    let bytes = key.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' => decoded.push(b' '),
+            b'%' if idx + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[idx + 1..idx + 3]).unwrap_or("");
+
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        idx += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        idx += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Resolves the location of a data file relative to its manifest.
+///
+/// Local data files are searched for beneath each ancestor of the manifest
+/// (matching a synced copy of the destination bucket), before falling back
+/// to a file of the same name alongside the manifest itself.
+fn resolve(manifest: &Location, contents: &Manifest, key: &str) -> Location {
+    match manifest {
+        Location::Remote { .. } => Location::Remote {
+            bucket: contents.destination_bucket.clone(),
+            key: key.to_string(),
+        },
+        Location::Local(path) => {
+            let parent = path.parent().unwrap_or_else(|| Path::new("."));
+            let nested = parent
+                .ancestors()
+                .map(|ancestor| ancestor.join(key))
+                .find(|candidate| candidate.is_file());
+
+            let sibling = || parent.join(Path::new(key).file_name().unwrap_or_default());
+
+            Location::Local(nested.unwrap_or_else(sibling))
+        }
+    }
+}
+
+/// Opens a reader to the contents at a location.
+fn open(s3: &S3Client, location: &Location) -> MetaResult<Box<dyn Read>> {
+    match location {
+        Location::Local(path) => Ok(Box::new(File::open(path)?)),
+        Location::Remote { bucket, key } => {
+            let request = GetObjectRequest {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                ..GetObjectRequest::default()
+            };
+
+            let response = s3.get_object(request).sync()?;
+            let body = response.body.ok_or("Inventory object has no body")?;
+
+            Ok(Box::new(BodyReader {
+                stream: body.wait(),
+                buffer: Vec::new(),
+                offset: 0,
+            }))
+        }
+    }
+}
+
+/// Blocking reader over the streaming body of an S3 object.
+struct BodyReader {
+    stream: Wait<StreamingBody>,
+    buffer: Vec<u8>,
+    offset: usize,
+}
+
+/// Read implementation.
+impl Read for BodyReader {
+    /// Reads from the current chunk, fetching the next chunk when exhausted.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset == self.buffer.len() {
+            match self.stream.next() {
+                None => return Ok(0),
+                Some(chunk) => {
+                    self.buffer = chunk?;
+                    self.offset = 0;
+                }
+            }
+        }
+
+        let count = buf.len().min(self.buffer.len() - self.offset);
+        buf[..count].copy_from_slice(&self.buffer[self.offset..self.offset + count]);
+        self.offset += count;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "columnar")]
+    use super::{Format, Pager};
+    use super::{Location, Manifest, Record};
+
+    #[test]
+    fn parsing_inventory_manifests() {
+        let manifest = Manifest::parse(
+            r#"{
+                "sourceBucket": "my.bucket",
+                "destinationBucket": "arn:aws:s3:::my.inventory",
+                "fileFormat": "CSV",
+                "fileSchema": "Bucket, Key, Size, LastModifiedDate",
+                "files": [{ "key": "my.bucket/all/data/one.csv.gz" }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.source_bucket, "my.bucket");
+        assert_eq!(manifest.destination_bucket, "my.inventory");
+        assert_eq!(
+            manifest.file_schema,
+            vec!["Bucket", "Key", "Size", "LastModifiedDate"]
+        );
+        assert_eq!(manifest.files, vec!["my.bucket/all/data/one.csv.gz"]);
+    }
+
+    #[test]
+    fn parsing_inventory_rows() {
+        let schema: Vec<String> = vec!["Bucket", "Key", "IsLatest", "Size", "LastModifiedDate"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let latest = [
+            "my.bucket",
+            "my%20dir/file+name.txt",
+            "true",
+            "512",
+            "2018-01-01T00:00:00.000Z",
+        ];
+        let previous = ["my.bucket", "file.txt", "false", "5", ""];
+
+        let (object, record) = super::parse_row(&schema, &latest, true).unwrap();
+
+        assert_eq!(object.key, Some("my dir/file name.txt".into()));
+        assert_eq!(object.size, Some(512));
+        assert_eq!(
+            object.last_modified,
+            Some("2018-01-01T00:00:00.000Z".into())
+        );
+        assert_eq!(record, Record::default());
+        assert!(super::parse_row(&schema, &previous, true).is_none());
+    }
+
+    #[test]
+    fn parsing_columnar_inventory_rows() {
+        let schema: Vec<String> = vec![
+            "key",
+            "size",
+            "storage_class",
+            "encryption_status",
+            "object_lock_legal_hold_status",
+            "intelligent_tiering_access_tier",
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let row = [
+            "my+file.txt",
+            "5",
+            "INTELLIGENT_TIERING",
+            "SSE-S3",
+            "OFF",
+            "ARCHIVE",
+        ];
+        let (object, record) = super::parse_row(&schema, &row, false).unwrap();
+
+        assert_eq!(object.key, Some("my+file.txt".into()));
+        assert_eq!(object.storage_class, Some("INTELLIGENT_TIERING".into()));
+        assert_eq!(record.encryption_status, Some("SSE-S3".into()));
+        assert_eq!(record.object_lock_legal_hold, Some("OFF".into()));
+        assert_eq!(record.access_tier, Some("ARCHIVE".into()));
+        assert_eq!(record.replication_status, None);
+    }
+
+    #[test]
+    fn parsing_inventory_locations() {
+        assert_eq!(
+            Location::parse("s3://my.inventory/path/manifest.json"),
+            Location::Remote {
+                bucket: "my.inventory".into(),
+                key: "path/manifest.json".into(),
+            }
+        );
+        assert_eq!(
+            Location::parse("./manifest.json"),
+            Location::Local("./manifest.json".into())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "columnar")]
+    fn reading_parquet_data_files() {
+        use arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+        use parquet::arrow::ArrowWriter;
+        use rusoto_core::Region;
+        use rusoto_s3::{Object, S3Client};
+        use std::fs::{self, File};
+        use std::sync::Arc;
+        use std::{env, process};
+
+        let path = env::temp_dir().join(format!("s3-meta-inventory-{}.parquet", process::id()));
+
+        // write a small data file using columnar names
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "key",
+                Arc::new(StringArray::from(vec!["logs/a.txt", "data/b.txt"])) as ArrayRef,
+            ),
+            ("size", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "encryption_status",
+                Arc::new(StringArray::from(vec![Some("SSE-S3"), None])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), None).unwrap();
+
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        // read it back through the same path as a manifest would
+        let s3 = S3Client::new(Region::UsEast1);
+        let mut objects = Vec::new();
+        let mut records = Vec::new();
+        let prefix = "logs/".to_string();
+
+        {
+            let mut pager = Pager {
+                prefix: Some(&prefix),
+                objects: Vec::new(),
+                records: Vec::new(),
+                handler: |page: &[Object], rows: &[Record]| {
+                    objects.extend_from_slice(page);
+                    records.extend_from_slice(rows);
+                },
+            };
+
+            let location = Location::Local(path.clone());
+
+            super::read_columnar(&s3, &location, &Format::Parquet, &mut pager).unwrap();
+            pager.flush();
+        }
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].key, Some("logs/a.txt".into()));
+        assert_eq!(objects[0].size, Some(1));
+        assert_eq!(records[0].encryption_status, Some("SSE-S3".into()));
+    }
+}
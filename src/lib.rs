@@ -0,0 +1,57 @@
+//! Metadata gathering for S3 buckets and subsections.
+//!
+//! This library contains everything required to scan an Amazon S3 bucket
+//! (or subsection thereof) and gather metadata about the objects within.
+//! Objects are fed through a chain of `Metric` implementations, which write
+//! their statistics into a structured `Report`.
+//!
+//! The `s3-meta` binary is a thin command line interface on top of this
+//! library, so anything it can do can also be embedded elsewhere.
+#[cfg(feature = "columnar")]
+extern crate arrow;
+extern crate chrono;
+extern crate csv;
+extern crate flate2;
+extern crate futures;
+extern crate humantime;
+#[cfg(feature = "columnar")]
+extern crate orc_rust;
+#[cfg(feature = "columnar")]
+extern crate parquet;
+extern crate pretty_bytes;
+extern crate quick_xml;
+extern crate rand;
+extern crate regex;
+extern crate rusoto_core;
+extern crate rusoto_s3;
+extern crate rusqlite;
+#[macro_use]
+extern crate serde_json;
+extern crate toml;
+extern crate unicode_normalization;
+
+pub mod bounded;
+pub mod config;
+pub mod dates;
+pub mod deep;
+pub mod diff;
+pub mod history;
+pub mod inventory;
+pub mod lifecycle;
+pub mod listing;
+pub mod metrics;
+pub mod options;
+pub mod report;
+pub mod sample;
+pub mod scan;
+pub mod source;
+pub mod types;
+
+mod util;
+
+pub use bounded::Bounded;
+pub use metrics::Metric;
+pub use options::Options;
+pub use report::{Report, Section};
+pub use scan::Scanner;
+pub use types::{MetaError, MetaResult};
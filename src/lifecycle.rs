@@ -0,0 +1,517 @@
+//! Lifecycle module for loading and evaluating bucket lifecycle rules.
+//!
+//! Rules can be loaded from local files (in either the XML format used by
+//! the S3 API, or the JSON format used by the AWS CLI) or fetched directly
+//! from a bucket. Only the current version actions are supported, as the
+//! listing contains no information about non-current object versions.
+use chrono::{DateTime, Duration, Utc};
+use futures::Future;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use rusoto_core::param::{Params, ServiceParams};
+use rusoto_core::request::{BufferedHttpResponse, HttpResponse};
+use rusoto_core::signature::SignedRequest;
+use rusoto_core::{Client, Region};
+use serde_json::{self, Value};
+use types::{MetaError, MetaResult};
+
+/// Monthly storage prices per GB in USD, based on `us-east-1` list prices.
+const PRICES: [(&str, f64); 8] = [
+    ("STANDARD", 0.023),
+    ("REDUCED_REDUNDANCY", 0.024),
+    ("INTELLIGENT_TIERING", 0.023),
+    ("STANDARD_IA", 0.0125),
+    ("ONEZONE_IA", 0.01),
+    ("GLACIER_IR", 0.004),
+    ("GLACIER", 0.0036),
+    ("DEEP_ARCHIVE", 0.00099),
+];
+
+/// Sources of lifecycle rules for a simulation.
+pub enum Source {
+    /// Rules should be fetched from the bucket being scanned.
+    Bucket,
+    /// Rules have already been loaded.
+    Rules(Vec<Rule>),
+}
+
+/// Representation of a single lifecycle rule.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rule {
+    /// The optional identifier of this rule.
+    pub id: Option<String>,
+    /// Whether this rule is currently enabled.
+    pub enabled: bool,
+    /// The key prefix this rule applies to.
+    pub prefix: String,
+    /// Whether this rule filters on tags (which listings do not include).
+    pub tagged: bool,
+    /// The size objects must exceed for this rule to apply.
+    pub min_size: Option<u64>,
+    /// The size objects must stay below for this rule to apply.
+    pub max_size: Option<u64>,
+    /// The transition actions attached to this rule.
+    pub transitions: Vec<Action>,
+    /// The expiration action attached to this rule, if any.
+    pub expiration: Option<Action>,
+}
+
+/// Representation of a lifecycle action (either a transition or expiration).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Action {
+    /// The number of days after creation this action applies.
+    pub days: Option<i64>,
+    /// The fixed date after which this action applies.
+    pub date: Option<DateTime<Utc>>,
+    /// The storage class to transition to, if a transition.
+    pub storage_class: Option<String>,
+}
+
+/// Outcomes of evaluating rules against an object.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome<'a> {
+    /// The object would be expired.
+    Expire,
+    /// The object would transition to the provided storage class.
+    Transition(&'a str),
+}
+
+/// Main implementation.
+impl Rule {
+    /// Determines whether this rule can be applied to an object.
+    ///
+    /// Rules which are disabled, or which filter on tags, never apply.
+    pub fn applies(&self, key: &str, size: u64) -> bool {
+        self.is_simulated()
+            && key.starts_with(&self.prefix)
+            && self.min_size.is_none_or(|min| size > min)
+            && self.max_size.is_none_or(|max| size < max)
+    }
+
+    /// Determines whether this rule can be simulated from a listing.
+    pub fn is_simulated(&self) -> bool {
+        self.enabled && !self.tagged
+    }
+
+    /// Evaluates this rule against an object modified at a given time.
+    ///
+    /// Expiration takes precedence over transitions; if several transitions
+    /// are due, the cheapest storage class is chosen (mirroring S3).
+    pub fn evaluate(
+        &self,
+        modified: &DateTime<Utc>,
+        reference: &DateTime<Utc>,
+    ) -> Option<Outcome<'_>> {
+        if let Some(ref expiration) = self.expiration {
+            if expiration.is_due(modified, reference) {
+                return Some(Outcome::Expire);
+            }
+        }
+
+        self.transitions
+            .iter()
+            .filter(|transition| transition.is_due(modified, reference))
+            .filter_map(|transition| transition.storage_class.as_ref())
+            .min_by(|left, right| compare_prices(left, right))
+            .map(|class| Outcome::Transition(class))
+    }
+}
+
+/// Main implementation.
+impl Action {
+    /// Determines whether this action is due for an object at a given time.
+    pub fn is_due(&self, modified: &DateTime<Utc>, reference: &DateTime<Utc>) -> bool {
+        if let Some(days) = self.days {
+            if *modified + Duration::days(days) <= *reference {
+                return true;
+            }
+        }
+        self.date.map(|date| date <= *reference).unwrap_or(false)
+    }
+}
+
+/// Retrieves the monthly price per GB of a storage class.
+pub fn price(storage_class: &str) -> Option<f64> {
+    PRICES
+        .iter()
+        .find(|(class, _)| *class == storage_class)
+        .map(|(_, price)| *price)
+}
+
+/// Compares two storage classes by price, with unknown classes last.
+pub fn compare_prices(left: &str, right: &str) -> std::cmp::Ordering {
+    let left = price(left).unwrap_or(f64::MAX);
+    let right = price(right).unwrap_or(f64::MAX);
+
+    left.partial_cmp(&right)
+        .expect("prices should be comparable")
+}
+
+/// Fetches the lifecycle rules attached to a bucket.
+///
+/// The raw XML configuration is requested rather than going through the
+/// `S3Client`, as Rusoto drops legacy rule prefixes and size filters.
+pub fn fetch(client: &Client, region: &Region, bucket: &str) -> MetaResult<Vec<Rule>> {
+    let path = format!("/{}", bucket);
+    let mut request = SignedRequest::new("GET", "s3", region, &path);
+    let mut params = Params::new();
+
+    params.put_key("lifecycle");
+    request.set_params(params);
+
+    let response = client.sign_and_dispatch(request, buffer).sync()?;
+    let body = String::from_utf8_lossy(&response.body);
+
+    if !response.status.is_success() {
+        return Err(MetaError::from_rusoto(body.into_owned()));
+    }
+
+    parse_xml(&body)
+}
+
+/// Buffers the body of a raw response from S3.
+fn buffer(
+    response: HttpResponse,
+) -> Box<dyn Future<Item = BufferedHttpResponse, Error = MetaError> + Send> {
+    Box::new(response.buffer().map_err(MetaError::from))
+}
+
+/// Parses lifecycle rules from either the XML or JSON format.
+pub fn parse(input: &str) -> MetaResult<Vec<Rule>> {
+    if input.trim_start().starts_with('<') {
+        parse_xml(input)
+    } else {
+        parse_json(input)
+    }
+}
+
+/// Parses lifecycle rules from the JSON format used by the AWS CLI.
+fn parse_json(input: &str) -> MetaResult<Vec<Rule>> {
+    // parse the input as an arbitrary JSON value
+    let value: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+
+    // the rules live in the `Rules` array at the top level
+    let rules = value["Rules"]
+        .as_array()
+        .ok_or("Lifecycle configuration has no Rules")?;
+
+    // parses an action from a JSON value
+    let action = |value: &Value| Action {
+        days: value["Days"].as_i64(),
+        date: value["Date"].as_str().and_then(::dates::parse),
+        storage_class: value["StorageClass"].as_str().map(|s| s.to_string()),
+    };
+
+    let rules = rules
+        .iter()
+        .map(|rule| {
+            let filter = &rule["Filter"];
+            let prefix = filter["Prefix"]
+                .as_str()
+                .or_else(|| filter["And"]["Prefix"].as_str())
+                .or_else(|| rule["Prefix"].as_str());
+
+            // size filters can live directly in the filter, or within `And`
+            let size = |field: &str| {
+                filter[field]
+                    .as_u64()
+                    .or_else(|| filter["And"][field].as_u64())
+            };
+
+            Rule {
+                id: rule["ID"].as_str().map(|s| s.to_string()),
+                enabled: rule["Status"] == "Enabled",
+                prefix: prefix.unwrap_or("").to_string(),
+                tagged: !filter["Tag"].is_null() || !filter["And"]["Tags"].is_null(),
+                min_size: size("ObjectSizeGreaterThan"),
+                max_size: size("ObjectSizeLessThan"),
+                transitions: rule["Transitions"]
+                    .as_array()
+                    .map(|transitions| transitions.iter().map(&action).collect())
+                    .unwrap_or_default(),
+                expiration: rule["Expiration"]
+                    .as_object()
+                    .map(|_| action(&rule["Expiration"])),
+            }
+        })
+        .collect();
+
+    Ok(rules)
+}
+
+/// Parses lifecycle rules from the XML format used by the S3 API.
+fn parse_xml(input: &str) -> MetaResult<Vec<Rule>> {
+    // create an XML reader and buffer
+    let mut reader = Reader::from_str(input);
+    let mut buffer = Vec::new();
+
+    reader.trim_text(true);
+
+    // stack of open elements, and rules parsed so far
+    let mut path: Vec<String> = Vec::new();
+    let mut rules: Vec<Rule> = Vec::new();
+
+    loop {
+        match reader.read_event(&mut buffer) {
+            // end of input, finish up
+            Ok(Event::Eof) => break,
+
+            // parse errors are fatal
+            Err(e) => return Err(format!("Invalid lifecycle XML: {}", e).into()),
+
+            // open elements are pushed to the stack
+            Ok(Event::Start(ref e)) => {
+                let name = reader.decode(e.local_name()).into_owned();
+
+                match name.as_str() {
+                    "Rule" => rules.push(Rule::default()),
+                    "Tag" => set_tagged(&mut rules),
+                    "Transition" => push_transition(&mut rules),
+                    "Expiration" => set_expiration(&mut rules),
+                    _ => (),
+                }
+
+                path.push(name);
+            }
+
+            // empty elements are only relevant for tags
+            Ok(Event::Empty(ref e)) if e.local_name() == b"Tag" => set_tagged(&mut rules),
+
+            // closing elements are popped from the stack
+            Ok(Event::End(_)) => {
+                path.pop();
+            }
+
+            // text values are assigned based on their parent elements
+            Ok(Event::Text(ref e)) => {
+                let text = e.unescape_and_decode(&reader).map_err(|e| e.to_string())?;
+                if let Some(rule) = rules.last_mut() {
+                    assign_xml_value(rule, &path, text);
+                }
+            }
+
+            // skip
+            _ => (),
+        }
+
+        // empty buffers
+        buffer.clear();
+    }
+
+    Ok(rules)
+}
+
+/// Assigns an XML text value to a rule, based on the element path.
+fn assign_xml_value(rule: &mut Rule, path: &[String], text: String) {
+    // trim everything up to (and including) the rule
+    let position = path.iter().rposition(|name| name == "Rule");
+    let path: Vec<&str> = match position {
+        Some(idx) => path[idx + 1..].iter().map(|s| s.as_str()).collect(),
+        None => return,
+    };
+
+    match path.as_slice() {
+        ["ID"] => rule.id = Some(text),
+        ["Status"] => rule.enabled = text == "Enabled",
+        ["Prefix"] | ["Filter", "Prefix"] | ["Filter", "And", "Prefix"] => rule.prefix = text,
+        ["Filter", "ObjectSizeGreaterThan"] | ["Filter", "And", "ObjectSizeGreaterThan"] => {
+            rule.min_size = text.parse().ok()
+        }
+        ["Filter", "ObjectSizeLessThan"] | ["Filter", "And", "ObjectSizeLessThan"] => {
+            rule.max_size = text.parse().ok()
+        }
+        ["Transition", field] => {
+            if let Some(transition) = rule.transitions.last_mut() {
+                assign_action_value(transition, field, text);
+            }
+        }
+        ["Expiration", field] => {
+            if let Some(ref mut expiration) = rule.expiration {
+                assign_action_value(expiration, field, text);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Assigns an XML text value to an action, based on the field name.
+fn assign_action_value(action: &mut Action, field: &str, text: String) {
+    match field {
+        "Days" => action.days = text.parse().ok(),
+        "Date" => action.date = ::dates::parse(&text),
+        "StorageClass" => action.storage_class = Some(text),
+        _ => (),
+    }
+}
+
+/// Marks the latest rule as filtering on tags.
+fn set_tagged(rules: &mut [Rule]) {
+    if let Some(rule) = rules.last_mut() {
+        rule.tagged = true;
+    }
+}
+
+/// Adds an empty transition to the latest rule.
+fn push_transition(rules: &mut [Rule]) {
+    if let Some(rule) = rules.last_mut() {
+        rule.transitions.push(Action::default());
+    }
+}
+
+/// Adds an empty expiration to the latest rule.
+fn set_expiration(rules: &mut [Rule]) {
+    if let Some(rule) = rules.last_mut() {
+        rule.expiration = Some(Action::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, Rule};
+    use chrono::{Duration, TimeZone, Utc};
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <LifecycleConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+            <Rule>
+                <ID>archive-logs</ID>
+                <Filter><Prefix>logs/</Prefix></Filter>
+                <Status>Enabled</Status>
+                <Transition><Days>30</Days><StorageClass>STANDARD_IA</StorageClass></Transition>
+                <Transition><Days>90</Days><StorageClass>GLACIER</StorageClass></Transition>
+                <Expiration><Days>365</Days></Expiration>
+            </Rule>
+            <Rule>
+                <ID>tagged</ID>
+                <Filter><Tag><Key>tier</Key><Value>cold</Value></Tag></Filter>
+                <Status>Enabled</Status>
+                <Expiration><Days>1</Days></Expiration>
+            </Rule>
+        </LifecycleConfiguration>"#;
+
+    const JSON: &str = r#"{
+        "Rules": [
+            {
+                "ID": "archive-logs",
+                "Filter": { "Prefix": "logs/" },
+                "Status": "Enabled",
+                "Transitions": [
+                    { "Days": 30, "StorageClass": "STANDARD_IA" },
+                    { "Days": 90, "StorageClass": "GLACIER" }
+                ],
+                "Expiration": { "Days": 365 }
+            },
+            {
+                "ID": "tagged",
+                "Filter": { "Tag": { "Key": "tier", "Value": "cold" } },
+                "Status": "Enabled",
+                "Expiration": { "Days": 1 }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parsing_lifecycle_formats() {
+        let xml = super::parse(XML).unwrap();
+        let json = super::parse(JSON).unwrap();
+
+        assert_eq!(xml, json);
+        assert_eq!(xml.len(), 2);
+
+        assert_eq!(xml[0].id, Some("archive-logs".into()));
+        assert_eq!(xml[0].prefix, "logs/");
+        assert_eq!(xml[0].transitions.len(), 2);
+        assert_eq!(xml[0].expiration.as_ref().unwrap().days, Some(365));
+        assert!(xml[0].enabled && !xml[0].tagged);
+        assert!(xml[1].tagged);
+    }
+
+    #[test]
+    fn evaluating_lifecycle_rules() {
+        let rules = super::parse(JSON).unwrap();
+        let rule: &Rule = &rules[0];
+
+        let reference = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
+        let modified = |days| reference - Duration::days(days);
+
+        assert!(rule.applies("logs/file.txt", 0));
+        assert!(!rule.applies("data/file.txt", 0));
+        assert!(!rules[1].applies("logs/file.txt", 0));
+
+        assert_eq!(rule.evaluate(&modified(10), &reference), None);
+        assert_eq!(
+            rule.evaluate(&modified(45), &reference),
+            Some(Outcome::Transition("STANDARD_IA"))
+        );
+        assert_eq!(
+            rule.evaluate(&modified(120), &reference),
+            Some(Outcome::Transition("GLACIER"))
+        );
+        assert_eq!(
+            rule.evaluate(&modified(400), &reference),
+            Some(Outcome::Expire)
+        );
+    }
+
+    #[test]
+    fn parsing_legacy_prefixes_and_size_filters() {
+        let xml = super::parse(
+            r#"<LifecycleConfiguration>
+                <Rule>
+                    <ID>legacy</ID>
+                    <Prefix>tmp/</Prefix>
+                    <Status>Enabled</Status>
+                    <Expiration><Days>7</Days></Expiration>
+                </Rule>
+                <Rule>
+                    <ID>sized</ID>
+                    <Filter>
+                        <And>
+                            <Prefix>data/</Prefix>
+                            <ObjectSizeGreaterThan>1024</ObjectSizeGreaterThan>
+                            <ObjectSizeLessThan>4096</ObjectSizeLessThan>
+                        </And>
+                    </Filter>
+                    <Status>Enabled</Status>
+                    <Expiration><Days>7</Days></Expiration>
+                </Rule>
+            </LifecycleConfiguration>"#,
+        )
+        .unwrap();
+
+        let json = super::parse(
+            r#"{
+                "Rules": [
+                    {
+                        "ID": "legacy",
+                        "Prefix": "tmp/",
+                        "Status": "Enabled",
+                        "Expiration": { "Days": 7 }
+                    },
+                    {
+                        "ID": "sized",
+                        "Filter": {
+                            "And": {
+                                "Prefix": "data/",
+                                "ObjectSizeGreaterThan": 1024,
+                                "ObjectSizeLessThan": 4096
+                            }
+                        },
+                        "Status": "Enabled",
+                        "Expiration": { "Days": 7 }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(xml, json);
+        assert_eq!(xml[0].prefix, "tmp/");
+        assert_eq!(xml[1].min_size, Some(1024));
+        assert_eq!(xml[1].max_size, Some(4096));
+
+        assert!(xml[0].applies("tmp/file.txt", 0));
+        assert!(!xml[1].applies("data/file.txt", 1024));
+        assert!(xml[1].applies("data/file.txt", 2048));
+        assert!(!xml[1].applies("data/file.txt", 4096));
+    }
+}
@@ -0,0 +1,223 @@
+//! Listing module to save and replay the objects seen during a scan.
+//!
+//! Objects are written as newline delimited JSON, using the same field
+//! names as the AWS CLI (`Key`, `Size`, `LastModified`, etc). Reading is
+//! more lenient, and also accepts JSON arrays of objects and documents
+//! containing a `Contents` array (such as `aws s3api list-objects-v2`).
+use rusoto_s3::{Object, Owner};
+use serde_json::{Deserializer, Value};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use types::MetaResult;
+
+/// The number of objects to pass through in each page.
+const PAGE_SIZE: usize = 1000;
+
+/// Writer structure to dump objects to a listing file.
+///
+/// Writes happen in the middle of a scan, so any failure is stored and
+/// reported when the writer is finished rather than aborting the scan.
+pub struct Writer {
+    writer: BufWriter<File>,
+    error: Option<io::Error>,
+}
+
+/// Main implementation.
+impl Writer {
+    /// Creates a new `Writer` to a file path.
+    pub fn create(path: &Path) -> MetaResult<Writer> {
+        Ok(Writer {
+            writer: BufWriter::new(File::create(path)?),
+            error: None,
+        })
+    }
+
+    /// Writes a page of objects to the listing.
+    pub fn write(&mut self, objects: &[Object]) {
+        if self.error.is_some() {
+            return;
+        }
+
+        for object in objects {
+            if let Err(e) = writeln!(self.writer, "{}", to_json(object)) {
+                self.error = Some(e);
+                return;
+            }
+        }
+    }
+
+    /// Flushes the listing, reporting any failures along the way.
+    pub fn finish(&mut self) -> MetaResult<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e.into());
+        }
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Reads all objects from a listing file, passing them through in pages.
+///
+/// Objects can optionally be filtered to those beneath a prefix.
+pub fn read<F>(path: &Path, prefix: Option<&String>, mut handler: F) -> MetaResult<()>
+where
+    F: FnMut(&[Object]),
+{
+    // buffer of the current page
+    let mut page = Vec::with_capacity(PAGE_SIZE);
+
+    // values can be split across lines, so stream values rather than lines
+    let reader = BufReader::new(File::open(path)?);
+    let values = Deserializer::from_reader(reader).into_iter::<Value>();
+
+    for value in values {
+        let value = value.map_err(|e| e.to_string())?;
+
+        // unwrap any documents or arrays of objects
+        let objects = match value {
+            Value::Array(objects) => objects,
+            Value::Object(ref map) if map.contains_key("Contents") => {
+                value["Contents"].as_array().cloned().unwrap_or_default()
+            }
+            value => vec![value],
+        };
+
+        for object in objects {
+            let object = from_json(&object).ok_or("Invalid object found in listing")?;
+
+            // filter out anything outside of the prefix
+            if let Some(prefix) = prefix {
+                if !object
+                    .key
+                    .as_ref()
+                    .is_some_and(|key| key.starts_with(prefix.as_str()))
+                {
+                    continue;
+                }
+            }
+
+            page.push(object);
+
+            // flush full pages
+            if page.len() == PAGE_SIZE {
+                handler(&page);
+                page.clear();
+            }
+        }
+    }
+
+    // flush the final page
+    if !page.is_empty() {
+        handler(&page);
+    }
+
+    Ok(())
+}
+
+/// Converts an `Object` into its JSON representation.
+fn to_json(object: &Object) -> Value {
+    let mut value = json!({
+        "Key": object.key,
+        "Size": object.size,
+        "LastModified": object.last_modified,
+    });
+
+    // optional fields are only written when present
+    if let Some(ref e_tag) = object.e_tag {
+        value["ETag"] = json!(e_tag);
+    }
+
+    if let Some(ref storage_class) = object.storage_class {
+        value["StorageClass"] = json!(storage_class);
+    }
+
+    if let Some(ref owner) = object.owner {
+        value["Owner"] = json!({
+            "ID": owner.id,
+            "DisplayName": owner.display_name,
+        });
+    }
+
+    value
+}
+
+/// Converts a JSON representation back into an `Object`.
+///
+/// Objects must contain at least a key; sizes default to zero.
+fn from_json(value: &Value) -> Option<Object> {
+    let string = |value: &Value| value.as_str().map(|s| s.to_string());
+    let owner = &value["Owner"];
+
+    Some(Object {
+        key: Some(string(&value["Key"])?),
+        size: Some(value["Size"].as_i64().unwrap_or(0)),
+        last_modified: Some(string(&value["LastModified"]).unwrap_or_default()),
+        e_tag: string(&value["ETag"]),
+        storage_class: string(&value["StorageClass"]),
+        owner: if owner.is_object() {
+            Some(Owner {
+                id: string(&owner["ID"]),
+                display_name: string(&owner["DisplayName"]),
+            })
+        } else {
+            None
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rusoto_s3::{Object, Owner};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn converting_objects_to_json() {
+        let object = Object {
+            key: Some("dir/file.txt".into()),
+            size: Some(512),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            e_tag: Some("\"abc\"".into()),
+            storage_class: Some("STANDARD".into()),
+            owner: Some(Owner {
+                id: Some("1234".into()),
+                display_name: None,
+            }),
+        };
+
+        let value = super::to_json(&object);
+
+        assert_eq!(value["Key"], "dir/file.txt");
+        assert_eq!(value["Owner"]["ID"], "1234");
+        assert_eq!(super::from_json(&value), Some(object));
+    }
+
+    #[test]
+    fn reading_listing_files() {
+        let path = env::temp_dir().join(format!("s3-meta-listing-{}", std::process::id()));
+
+        fs::write(
+            &path,
+            concat!(
+                "{\"Key\":\"logs/a.txt\",\"Size\":1}\n",
+                "{\"Key\":\"data/b.txt\",\"Size\":2}\n",
+                "{\"Contents\":[\n  {\"Key\":\"logs/c.txt\",\"Size\":3}\n]}\n",
+            ),
+        )
+        .unwrap();
+
+        let mut all = Vec::new();
+        let mut logs = Vec::new();
+
+        super::read(&path, None, |page| all.extend_from_slice(page)).unwrap();
+        super::read(&path, Some(&"logs/".into()), |page| {
+            logs.extend_from_slice(page)
+        })
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(all.len(), 3);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].size, Some(3));
+    }
+}
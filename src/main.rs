@@ -5,31 +5,39 @@
 //!
 //! Credentials must be provided via guidelines in the [AWS Documentation]
 //! (https://docs.aws.amazon.com/cli/latest/userguide/cli-environment.html).
+extern crate chrono;
+extern crate clap;
 extern crate humantime;
-extern crate pretty_bytes;
-extern crate quick_xml;
 extern crate rusoto_core;
 extern crate rusoto_s3;
+extern crate s3_meta;
 
-use rusoto_core::{credential::ChainProvider, region::Region, HttpClient};
-use rusoto_s3::{ListObjectsV2Request, S3Client, S3};
+use chrono::Utc;
+use rusoto_core::{credential::ChainProvider, region::Region, Client, HttpClient};
+use rusoto_s3::S3Client;
+use s3_meta::{diff, history, lifecycle, scan, types};
 use std::time::Duration;
 
-mod bounded;
-mod metrics;
-mod types;
-mod util;
+mod cli;
 
 fn main() -> types::MetaResult<()> {
-    // grab the root path of the S3 location to use
-    let root_paths = std::env::args().nth(1).ok_or("Bucket name not provided")?;
-
-    // split the path up to a (bucket, prefix)
-    let mut splitn = root_paths.trim_start_matches("s3://").splitn(2, '/');
-
-    // bucket is required, prefix is optional after `/`
-    let bucket = splitn.next().unwrap().to_string();
-    let prefix = splitn.next().map(|s| s.to_string());
+    // parse the command from the command line
+    let mut options = match cli::Command::from_env()? {
+        cli::Command::Scan(options) => *options,
+        cli::Command::Diff { old, new, limit } => {
+            print!("{}", diff::run(&old, &new, limit)?);
+            return Ok(());
+        }
+        cli::Command::History {
+            database,
+            bucket,
+            prefix,
+            formatter,
+        } => {
+            print!("{}", history::run(&database, &bucket, &prefix, &formatter)?);
+            return Ok(());
+        }
+    };
 
     // create client options
     let client = HttpClient::new()?;
@@ -39,50 +47,31 @@ fn main() -> types::MetaResult<()> {
     let mut chain = ChainProvider::new();
     chain.set_timeout(Duration::from_millis(500));
 
+    // fetch the bucket lifecycle configuration, if requested
+    if let Some(lifecycle::Source::Bucket) = options.lifecycle {
+        // skip the request when the simulation isn't reported
+        if options.selects("lifecycle") {
+            let raw = Client::new_with(chain.clone(), HttpClient::new()?);
+            let rules = lifecycle::fetch(&raw, &region, &options.bucket)?;
+            options.lifecycle = Some(lifecycle::Source::Rules(rules));
+        }
+    }
+
     // construct new S3 client
     let s3 = S3Client::new_with(client, chain, region);
 
-    // create our set of metric meters
-    let mut chain = metrics::chain(&prefix);
-
-    // iteration token
-    let mut token = None;
+    // create a scanner to walk the bucket
+    let mut scanner = scan::Scanner::new(&s3, &options);
 
-    loop {
-        // create a request to list objects
-        let request = ListObjectsV2Request {
-            bucket: bucket.clone(),
-            prefix: prefix.clone(),
-            continuation_token: token,
-            ..ListObjectsV2Request::default()
-        };
-
-        // execute the request and await the response (blocking)
-        let response = s3.list_objects_v2(request).sync()?;
-
-        // check contents (although should always be there)
-        if let Some(contents) = response.contents {
-            // iterate all objects
-            for entry in contents {
-                // iterate all metrics meters
-                for metric in &mut chain {
-                    metric.register(&entry);
-                }
-            }
-        }
-
-        // break if there's no way to continue
-        if response.next_continuation_token.is_none() {
-            break;
-        }
-
-        // store the token for next iteration
-        token = response.next_continuation_token;
-    }
+    // run the scan and print all statistics
+    scanner.run()?;
+    let report = scanner.report();
+    print!("{}", report);
 
-    // print all statistics
-    for metric in &chain {
-        metric.print();
+    // append the results to the history, if requested
+    if let Some(ref database) = options.history {
+        let (bucket, prefix) = options.history_key();
+        history::record(database, &bucket, &prefix, Utc::now(), &report)?;
     }
 
     // done
@@ -0,0 +1,158 @@
+//! Age metrics tracking for S3 objects.
+use chrono::{DateTime, Utc};
+use dates::Formatter;
+use rusoto_s3::Object;
+
+use super::Metric;
+use report::Report;
+
+/// Number of seconds in a day, used to define bucket bounds.
+const DAY: u64 = 86_400;
+
+/// Labels and exclusive upper bounds (in seconds) of each age bucket.
+///
+/// Any object older than the final bound is placed into an additional
+/// `older` bucket, so there is always one more bucket than bound.
+const BOUNDS: [(&str, u64); 5] = [
+    ("under_1d", DAY),
+    ("under_7d", DAY * 7),
+    ("under_30d", DAY * 30),
+    ("under_90d", DAY * 90),
+    ("under_1y", DAY * 365),
+];
+
+/// Container struct for age metrics tracked by S3.
+pub struct Age {
+    reference: DateTime<Utc>,
+    formatter: Formatter,
+    file_counts: [u64; 6],
+    file_bytes: [u64; 6],
+    unknown: (u64, u64),
+}
+
+/// Main implementation.
+impl Age {
+    /// Constructs a new `Age` struct.
+    pub fn new(reference: DateTime<Utc>, formatter: &Formatter) -> Age {
+        Age {
+            reference,
+            formatter: formatter.clone(),
+            file_counts: [0; 6],
+            file_bytes: [0; 6],
+            unknown: (0, 0),
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for Age {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        // parse the modification time, counting anything unreadable
+        let modified = match super::get_modified(object) {
+            Some(modified) => modified,
+            None => {
+                self.unknown.0 += 1;
+                self.unknown.1 += super::get_size(object);
+                return;
+            }
+        };
+
+        // objects modified after the reference count as zero age
+        let age = self
+            .reference
+            .signed_duration_since(modified)
+            .num_seconds()
+            .max(0) as u64;
+
+        // locate the bucket and increment
+        let index = bucket_index(age);
+
+        self.file_counts[index] += 1;
+        self.file_bytes[index] += super::get_size(object);
+    }
+
+    /// Reports all internal statistics under the `age` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: age
+        report.head("age");
+
+        // log out the time that all ages are relative to
+        report.pair("reference_time", self.formatter.format(&self.reference));
+
+        // bounded labels, followed by everything remaining
+        let labels = BOUNDS.iter().map(|(label, _)| *label).chain(Some("older"));
+
+        // log out the counts and sizes of each bucket
+        for (idx, label) in labels.enumerate() {
+            let bytes = self.file_bytes[idx];
+
+            report.pair(&format!("{}_files", label), self.file_counts[idx]);
+            report.pair(&format!("{}_size", label), ::util::convert_bytes(bytes));
+            report.pair(&format!("{}_bytes", label), bytes);
+        }
+
+        // log out anything without a readable modification time
+        report.pair("unknown_files", self.unknown.0);
+        report.pair("unknown_size", ::util::convert_bytes(self.unknown.1));
+        report.pair("unknown_bytes", self.unknown.1);
+    }
+}
+
+/// Locates the index of the bucket an age (in seconds) belongs to.
+fn bucket_index(age: u64) -> usize {
+    BOUNDS
+        .iter()
+        .position(|(_, bound)| age < *bound)
+        .unwrap_or(BOUNDS.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Age, DAY};
+    use chrono::{TimeZone, Utc};
+    use dates::Formatter;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(modified: &str, size: i64) -> Object {
+        Object {
+            key: Some("file.txt".into()),
+            size: Some(size),
+            last_modified: Some(modified.into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn locating_age_buckets() {
+        assert_eq!(super::bucket_index(0), 0);
+        assert_eq!(super::bucket_index(DAY - 1), 0);
+        assert_eq!(super::bucket_index(DAY), 1);
+        assert_eq!(super::bucket_index(DAY * 29), 2);
+        assert_eq!(super::bucket_index(DAY * 89), 3);
+        assert_eq!(super::bucket_index(DAY * 364), 4);
+        assert_eq!(super::bucket_index(DAY * 365), 5);
+    }
+
+    #[test]
+    fn reporting_unknown_ages() {
+        let reference = Utc.with_ymd_and_hms(2018, 1, 10, 0, 0, 0).unwrap();
+        let mut metric = Age::new(reference, &Formatter::default());
+
+        metric.register(&object("2018-01-09T12:00:00.000Z", 1));
+        metric.register(&object("2018-01-01T00:00:00.000Z", 2));
+        metric.register(&object("yesterday", 4));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("age", label);
+
+        assert_eq!(get("under_1d_files"), Some("1"));
+        assert_eq!(get("under_30d_bytes"), Some("2"));
+        assert_eq!(get("unknown_files"), Some("1"));
+        assert_eq!(get("unknown_bytes"), Some("4"));
+    }
+}
@@ -0,0 +1,237 @@
+//! Key collision metrics tracking for S3 objects.
+use rusoto_s3::Object;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use unicode_normalization::UnicodeNormalization;
+
+use super::Metric;
+use report::Report;
+
+/// The number of entries to include in each list.
+const LIMIT: usize = 10;
+
+/// Container struct for key collision metrics.
+///
+/// Keys are folded into the form a case-insensitive, normalizing file
+/// system would see. Folded keys are stored as hashes alongside the first
+/// original key folding to each, so every member of a group can be listed.
+pub struct Collisions {
+    files: HashMap<u128, String>,
+    folders: HashSet<u128>,
+    groups: HashMap<u128, BTreeSet<String>>,
+    conflicts: BTreeSet<String>,
+}
+
+/// Main implementation.
+impl Collisions {
+    /// Constructs a new `Collisions` struct.
+    pub fn new() -> Collisions {
+        Collisions {
+            files: HashMap::new(),
+            folders: HashSet::new(),
+            groups: HashMap::new(),
+            conflicts: BTreeSet::new(),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Collisions {
+    /// Constructs a new `Collisions` struct.
+    fn default() -> Collisions {
+        Collisions::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Collisions {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let folded = fold(key);
+
+        // check each ancestor folder against existing files
+        for (idx, _) in folded.match_indices('/') {
+            let folder = hash(&folded[..idx]);
+
+            if self.folders.insert(folder) && self.files.contains_key(&folder) {
+                self.conflicts
+                    .insert(key[..original_index(key, &folded, idx)].to_string());
+            }
+        }
+
+        // folder markers are folders, rather than files
+        if key.ends_with('/') {
+            return;
+        }
+
+        let file = hash(&folded);
+
+        // check the file against existing folders
+        if self.folders.contains(&file) {
+            self.conflicts.insert(key.to_string());
+        }
+
+        // only store the first key folding to each value
+        let first = match self.files.entry(file) {
+            Entry::Vacant(entry) => {
+                entry.insert(key.to_string());
+                return;
+            }
+            Entry::Occupied(entry) => entry.into_mut(),
+        };
+
+        // the same key can't collide with itself
+        if first == key {
+            return;
+        }
+
+        // another key folds to the same value, so they collide
+        let group = self.groups.entry(file).or_insert_with(|| {
+            let mut group = BTreeSet::new();
+            group.insert(first.clone());
+            group
+        });
+
+        group.insert(key.to_string());
+    }
+
+    /// Reports all internal statistics under the `collisions` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: collisions
+        report.head("collisions");
+
+        // order groups by their keys, for deterministic output
+        let mut groups: Vec<&BTreeSet<String>> = self.groups.values().collect();
+        groups.sort();
+
+        let count = |name| groups.iter().filter(|group| kind(group) == name).count();
+
+        report.pair("case_groups", count("case"));
+        report.pair("normalization_groups", count("normalization"));
+        report.pair(
+            "colliding_files",
+            groups.iter().map(|group| group.len()).sum::<usize>(),
+        );
+
+        // log out each colliding group
+        for (idx, group) in groups.iter().take(LIMIT).enumerate() {
+            let label = format!("group_{}", idx + 1);
+
+            report.pair(&format!("{}_kind", label), kind(group));
+            report.pair(&format!("{}_files", label), group.len());
+
+            for (key_idx, key) in group.iter().enumerate() {
+                report.pair(&format!("{}_key_{}", label, key_idx + 1), key);
+            }
+        }
+
+        // log out any paths used as both a file and folder
+        report.pair("file_folder_conflicts", self.conflicts.len());
+
+        for (idx, name) in self.conflicts.iter().take(LIMIT).enumerate() {
+            report.pair(&format!("conflict_{}_name", idx + 1), name);
+        }
+    }
+}
+
+/// Folds a key into the form seen by case-insensitive, normalizing systems.
+fn fold(key: &str) -> String {
+    key.nfc().collect::<String>().to_lowercase()
+}
+
+/// Hashes a key into a 128-bit value, to make collisions unlikely.
+fn hash(key: &str) -> u128 {
+    let half = |seed: u8| {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        key.hash(&mut hasher);
+        hasher.finish()
+    };
+    (u128::from(half(0)) << 64) | u128::from(half(1))
+}
+
+/// Maps the index of a `/` in a folded key back to the original key.
+///
+/// Folding can change the length of a key, but never the separators.
+fn original_index(key: &str, folded: &str, idx: usize) -> usize {
+    let nth = folded[..idx].matches('/').count();
+
+    key.match_indices('/')
+        .nth(nth)
+        .map(|(idx, _)| idx)
+        .unwrap_or(key.len())
+}
+
+/// Determines the kind of a colliding group of keys.
+///
+/// Keys which are identical once normalized only differ by normalization,
+/// whereas anything else also differs by case.
+fn kind(group: &BTreeSet<String>) -> &'static str {
+    let normalized: HashSet<String> = group.iter().map(|key| key.nfc().collect()).collect();
+
+    if normalized.len() == 1 {
+        "normalization"
+    } else {
+        "case"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Collisions;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(1),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn detecting_collisions() {
+        let mut metric = Collisions::new();
+
+        for key in &[
+            "Logs/file.txt",
+            "logs/file.txt",
+            "logs/file.txt",
+            "caf\u{e9}.txt",
+            "cafe\u{301}.txt",
+            "data",
+            "data/file.txt",
+            "Other/file.txt",
+            "other",
+            "folder/",
+            "folder/file.txt",
+        ] {
+            metric.register(&object(key));
+        }
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("collisions", label);
+
+        assert_eq!(get("case_groups"), Some("1"));
+        assert_eq!(get("normalization_groups"), Some("1"));
+        assert_eq!(get("colliding_files"), Some("4"));
+        assert_eq!(get("group_1_kind"), Some("case"));
+        assert_eq!(get("group_1_files"), Some("2"));
+        assert_eq!(get("group_1_key_1"), Some("Logs/file.txt"));
+        assert_eq!(get("group_1_key_2"), Some("logs/file.txt"));
+        assert_eq!(get("group_2_kind"), Some("normalization"));
+        assert_eq!(get("group_2_files"), Some("2"));
+        assert_eq!(get("group_2_key_1"), Some("cafe\u{301}.txt"));
+        assert_eq!(get("group_2_key_2"), Some("caf\u{e9}.txt"));
+        assert_eq!(get("file_folder_conflicts"), Some("2"));
+        assert_eq!(get("conflict_1_name"), Some("data"));
+        assert_eq!(get("conflict_2_name"), Some("other"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Content header metrics tracking for S3 objects.
+use rusoto_s3::{HeadObjectOutput, Object};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Metric;
+use report::Report;
+
+/// Container struct for content header metrics tracked by S3.
+pub struct Content {
+    total_keys: u64,
+    inspected_keys: u64,
+    content_types: HashMap<String, (u64, u64)>,
+    missing_cache_control: u64,
+}
+
+/// Main implementation.
+impl Content {
+    /// Constructs a new `Content` struct.
+    pub fn new() -> Content {
+        Content {
+            total_keys: 0,
+            inspected_keys: 0,
+            content_types: HashMap::new(),
+            missing_cache_control: 0,
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Content {
+    /// Constructs a new `Content` struct.
+    fn default() -> Content {
+        Content::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Content {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, _object: &Object) {
+        self.total_keys += 1;
+    }
+
+    /// Registers the headers of an S3 `Object` with this metric struct.
+    fn register_head(&mut self, object: &Object, head: &HeadObjectOutput) {
+        // count another inspected key
+        self.inspected_keys += 1;
+
+        // objects without a content type fall back to the S3 default
+        let content_type = head
+            .content_type
+            .clone()
+            .unwrap_or_else(|| "binary/octet-stream".into());
+
+        // increment the content type counters
+        let entry = self.content_types.entry(content_type).or_insert((0, 0));
+
+        entry.0 += 1;
+        entry.1 += super::get_size(object);
+
+        // track missing cache headers
+        if head.cache_control.is_none() {
+            self.missing_cache_control += 1;
+        }
+    }
+
+    /// Reports all internal statistics under the `content` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: content
+        report.head("content");
+
+        // log out how many objects could be inspected
+        report.pair("inspected_files", self.inspected_keys);
+        report.pair("uninspected_files", self.total_keys - self.inspected_keys);
+        report.pair("missing_cache_control", self.missing_cache_control);
+        report.pair("unique_content_types", self.content_types.len());
+
+        // sort content types by their frequency, then by name
+        let mut content_types: Vec<_> = self.content_types.iter().collect();
+        content_types.sort_by_key(|(name, (files, _))| (Reverse(*files), *name));
+
+        // log out each content type, ranked by frequency
+        for (idx, (content_type, (files, bytes))) in content_types.into_iter().enumerate() {
+            let label = format!("content_type_{}", idx + 1);
+
+            report.pair(&label, content_type);
+            report.pair(&format!("{}_files", label), files);
+            report.pair(&format!("{}_bytes", label), bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Content;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::{HeadObjectOutput, Object};
+
+    #[test]
+    fn reporting_content_headers() {
+        let mut metric = Content::new();
+        let object = Object {
+            key: Some("file.txt".into()),
+            size: Some(10),
+            ..Object::default()
+        };
+
+        let json = HeadObjectOutput {
+            content_type: Some("application/json".into()),
+            cache_control: Some("no-cache".into()),
+            ..HeadObjectOutput::default()
+        };
+
+        for _ in 0..4 {
+            metric.register(&object);
+        }
+
+        metric.register_head(&object, &json);
+        metric.register_head(&object, &json);
+        metric.register_head(&object, &HeadObjectOutput::default());
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("content", label);
+
+        assert_eq!(get("inspected_files"), Some("3"));
+        assert_eq!(get("uninspected_files"), Some("1"));
+        assert_eq!(get("missing_cache_control"), Some("1"));
+        assert_eq!(get("unique_content_types"), Some("2"));
+        assert_eq!(get("content_type_1"), Some("application/json"));
+        assert_eq!(get("content_type_1_bytes"), Some("20"));
+        assert_eq!(get("content_type_2"), Some("binary/octet-stream"));
+    }
+}
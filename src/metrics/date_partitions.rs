@@ -0,0 +1,413 @@
+//! Date partition metrics tracking for S3 objects.
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use rusoto_s3::Object;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use super::Metric;
+use report::Report;
+
+/// The number of entries to include in each ranked list.
+const LIMIT: usize = 10;
+
+/// The number of neighbours on each side to compare a period against.
+const NEIGHBOURS: usize = 3;
+
+/// The ratio against the median of its neighbours to flag a small period.
+const SMALL_RATIO: f64 = 0.25;
+
+/// Granularity of the dates found within a series.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Granularity {
+    Day,
+    Hour,
+}
+
+/// Main implementation.
+impl Granularity {
+    /// Retrieves the name of the granularity, for output.
+    fn name(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Hour => "hour",
+        }
+    }
+
+    /// Retrieves the duration between two consecutive periods.
+    fn step(self) -> Duration {
+        match self {
+            Granularity::Day => Duration::days(1),
+            Granularity::Hour => Duration::hours(1),
+        }
+    }
+
+    /// Formats a period of this granularity, for output.
+    fn format(self, period: &NaiveDateTime) -> String {
+        match self {
+            Granularity::Day => period.format("%Y-%m-%d").to_string(),
+            Granularity::Hour => period.format("%Y-%m-%dT%H").to_string(),
+        }
+    }
+}
+
+/// Container struct for date partitioned layout metrics.
+///
+/// Keys are grouped into series by the prefix before their date segments
+/// (e.g. `logs/2018/01/01/` or `logs/dt=2018-01-01/` both belong to the
+/// `logs/` series), with files and bytes tracked per day or hour.
+pub struct DatePartitions {
+    series: HashMap<(String, Granularity), BTreeMap<NaiveDateTime, (u64, u64)>>,
+    undated: (u64, u64),
+}
+
+/// Main implementation.
+impl DatePartitions {
+    /// Constructs a new `DatePartitions` struct.
+    pub fn new() -> DatePartitions {
+        DatePartitions {
+            series: HashMap::new(),
+            undated: (0, 0),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for DatePartitions {
+    /// Constructs a new `DatePartitions` struct.
+    fn default() -> DatePartitions {
+        DatePartitions::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for DatePartitions {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let size = super::get_size(object);
+
+        // locate the date of the key, if any
+        let (prefix, period, granularity) = match detect(key) {
+            Some(detected) => detected,
+            None => {
+                self.undated.0 += 1;
+                self.undated.1 += size;
+                return;
+            }
+        };
+
+        let totals = self
+            .series
+            .entry((prefix.to_string(), granularity))
+            .or_default()
+            .entry(period)
+            .or_insert((0, 0));
+
+        totals.0 += 1;
+        totals.1 += size;
+    }
+
+    /// Reports all internal statistics under the `date_partitions` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: date_partitions
+        report.head("date_partitions");
+
+        let totals = self.series.values().flat_map(|periods| periods.values());
+
+        report.pair("total_series", self.series.len());
+        report.pair("dated_files", totals.clone().map(|t| t.0).sum::<u64>());
+        report.pair("dated_bytes", totals.map(|t| t.1).sum::<u64>());
+        report.pair("undated_files", self.undated.0);
+        report.pair("undated_bytes", self.undated.1);
+
+        // rank series by their total bytes
+        let mut series: Vec<_> = self
+            .series
+            .iter()
+            .map(|(name, periods)| (name, periods, periods.values().map(|t| t.1).sum::<u64>()))
+            .collect();
+
+        series.sort_by_key(|(name, _, bytes)| (Reverse(*bytes), *name));
+
+        for (idx, ((prefix, granularity), periods, bytes)) in series.iter().take(LIMIT).enumerate()
+        {
+            let label = format!("series_{}", idx + 1);
+            let first = periods.keys().next().expect("series should have periods");
+            let last = periods
+                .keys()
+                .next_back()
+                .expect("series should have periods");
+
+            report.pair(&format!("{}_name", label), prefix);
+            report.pair(&format!("{}_granularity", label), granularity.name());
+            report.pair(&format!("{}_first", label), granularity.format(first));
+            report.pair(&format!("{}_last", label), granularity.format(last));
+            report.pair(&format!("{}_periods", label), periods.len());
+            report.pair(&format!("{}_bytes", label), bytes);
+
+            // log out any ranges of missing periods
+            let gaps = gaps(periods, *granularity);
+            let missing: i64 = gaps
+                .iter()
+                .map(|(from, to)| {
+                    (*to - *from).num_seconds() / granularity.step().num_seconds() + 1
+                })
+                .sum();
+
+            report.pair(&format!("{}_missing_periods", label), missing);
+
+            for (gap, (from, to)) in gaps.iter().take(LIMIT).enumerate() {
+                let gap_label = format!("{}_gap_{}", label, gap + 1);
+
+                report.pair(&format!("{}_from", gap_label), granularity.format(from));
+                report.pair(&format!("{}_to", gap_label), granularity.format(to));
+            }
+
+            // log out any abnormally small periods
+            let small = small(periods);
+
+            report.pair(&format!("{}_small_periods", label), small.len());
+
+            for (period, (date, bytes, median)) in small.iter().take(LIMIT).enumerate() {
+                let small_label = format!("{}_small_{}", label, period + 1);
+
+                report.pair(&format!("{}_name", small_label), granularity.format(date));
+                report.pair(&format!("{}_bytes", small_label), bytes);
+                report.pair(&format!("{}_neighbour_bytes", small_label), median);
+            }
+        }
+    }
+}
+
+/// Detects the date of a key, returning the prefix before the date.
+///
+/// Recognised folder layouts are `yyyy/mm/dd`, `yyyy-mm-dd` and `yyyymmdd`,
+/// optionally followed by an `hh` folder. Segments can also be written in
+/// a `key=value` form, such as `dt=2018-01-01` or `year=2018/month=01`,
+/// in which case months, days and hours can drop their leading zero.
+fn detect(key: &str) -> Option<(&str, NaiveDateTime, Granularity)> {
+    // only folders can hold a date, so skip the file name
+    let mut offsets = Vec::new();
+    let mut segments = Vec::new();
+    let mut offset = 0;
+
+    while let Some(idx) = key[offset..].find('/') {
+        let segment = &key[offset..offset + idx];
+
+        // keep track of whether the segment was a named value
+        let value = segment.rsplit('=').next().unwrap_or(segment);
+
+        offsets.push(offset);
+        segments.push((value, value.len() != segment.len()));
+        offset += idx + 1;
+    }
+
+    for idx in 0..segments.len() {
+        // single segment dates, or dates split across three segments
+        let (date, used) = match parse_date(segments[idx].0) {
+            Some(date) => (date, 1),
+            None => match segments.get(idx..idx + 3) {
+                Some(&[(year, _), (month, keyed), (day, _)]) => {
+                    match parse_split(year, month, day, keyed) {
+                        Some(date) => (date, 3),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            },
+        };
+
+        let start = date.and_hms_opt(0, 0, 0).expect("midnight should be valid");
+        let prefix = &key[..offsets[idx]];
+
+        // check for a trailing hour segment
+        let hour = segments
+            .get(idx + used)
+            .filter(|(segment, keyed)| segment.len() == 2 || (*keyed && segment.len() == 1))
+            .and_then(|(segment, _)| segment.parse::<i64>().ok())
+            .filter(|hour| *hour < 24);
+
+        return Some(match hour {
+            Some(hour) => (prefix, start + Duration::hours(hour), Granularity::Hour),
+            None => (prefix, start, Granularity::Day),
+        });
+    }
+
+    None
+}
+
+/// Parses a date from a single `yyyy-mm-dd` or `yyyymmdd` segment.
+fn parse_date(segment: &str) -> Option<NaiveDate> {
+    match segment.len() {
+        8 if segment.bytes().all(|b| b.is_ascii_digit()) => {
+            parse_split(&segment[..4], &segment[4..6], &segment[6..], false)
+        }
+        10 => NaiveDate::parse_from_str(segment, "%Y-%m-%d")
+            .ok()
+            .filter(|date| is_plausible(*date)),
+        _ => None,
+    }
+}
+
+/// Parses a date from separate year, month and day segments.
+///
+/// Named segments (such as `month=1`) are allowed to drop leading zeros,
+/// as the name already makes it clear they're part of a date.
+fn parse_split(year: &str, month: &str, day: &str, keyed: bool) -> Option<NaiveDate> {
+    let width = |value: &str| value.len() == 2 || (keyed && value.len() == 1);
+
+    // enforce fixed widths to avoid matching arbitrary numbers
+    if year.len() != 4 || !width(month) || !width(day) {
+        return None;
+    }
+
+    let date = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)?;
+
+    Some(date).filter(|date| is_plausible(*date))
+}
+
+/// Determines whether a date is plausible as a partition of data.
+fn is_plausible(date: NaiveDate) -> bool {
+    (1970..2100).contains(&date.year())
+}
+
+/// Finds all ranges of missing periods between the first and last periods.
+fn gaps<V>(
+    periods: &BTreeMap<NaiveDateTime, V>,
+    granularity: Granularity,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let step = granularity.step();
+    let mut gaps = Vec::new();
+
+    for (current, next) in periods.keys().zip(periods.keys().skip(1)) {
+        if *next - *current > step {
+            gaps.push((*current + step, *next - step));
+        }
+    }
+
+    gaps
+}
+
+/// Finds all periods which are abnormally small compared to their neighbours.
+///
+/// Each period is compared against the median bytes of the periods on
+/// either side of it, ignoring any missing periods.
+fn small(periods: &BTreeMap<NaiveDateTime, (u64, u64)>) -> Vec<(NaiveDateTime, u64, u64)> {
+    let entries: Vec<(&NaiveDateTime, u64)> = periods.iter().map(|(k, v)| (k, v.1)).collect();
+    let mut small = Vec::new();
+
+    for (idx, (period, bytes)) in entries.iter().enumerate() {
+        let mut neighbours: Vec<u64> = entries[idx.saturating_sub(NEIGHBOURS)..idx]
+            .iter()
+            .chain(entries.iter().skip(idx + 1).take(NEIGHBOURS))
+            .map(|(_, bytes)| *bytes)
+            .collect();
+
+        // a single neighbour isn't enough to judge against
+        if neighbours.len() < 2 {
+            continue;
+        }
+
+        neighbours.sort_unstable();
+
+        let median = neighbours[neighbours.len() / 2];
+
+        if (*bytes as f64) < median as f64 * SMALL_RATIO {
+            small.push((**period, *bytes, median));
+        }
+    }
+
+    small
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatePartitions, Granularity};
+    use chrono::NaiveDate;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str, size: i64) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn detecting_dates() {
+        let day = NaiveDate::from_ymd_opt(2018, 1, 2).unwrap();
+        let midnight = day.and_hms_opt(0, 0, 0).unwrap();
+        let hour = day.and_hms_opt(5, 0, 0).unwrap();
+
+        assert_eq!(
+            super::detect("logs/2018/01/02/file.txt"),
+            Some(("logs/", midnight, Granularity::Day))
+        );
+        assert_eq!(
+            super::detect("logs/2018/01/02/05/file.txt"),
+            Some(("logs/", hour, Granularity::Hour))
+        );
+        assert_eq!(
+            super::detect("data/dt=2018-01-02/file.txt"),
+            Some(("data/", midnight, Granularity::Day))
+        );
+        assert_eq!(
+            super::detect("year=2018/month=01/day=02/file.txt"),
+            Some(("", midnight, Granularity::Day))
+        );
+        assert_eq!(
+            super::detect("year=2018/month=1/day=2/hour=5/file.txt"),
+            Some(("", hour, Granularity::Hour))
+        );
+        assert_eq!(
+            super::detect("20180102/file.txt"),
+            Some(("", midnight, Granularity::Day))
+        );
+        assert_eq!(super::detect("logs/2018/13/02/file.txt"), None);
+        assert_eq!(super::detect("logs/2018/1/2/file.txt"), None);
+        assert_eq!(super::detect("logs/12345678/file.txt"), None);
+        assert_eq!(super::detect("logs/2018-01-02.txt"), None);
+    }
+
+    #[test]
+    fn reporting_gaps_and_small_periods() {
+        let mut metric = DatePartitions::new();
+
+        for day in &[1, 2, 3, 6, 7, 9] {
+            metric.register(&object(&format!("logs/2018/01/{:02}/file.txt", day), 100));
+        }
+
+        metric.register(&object("logs/2018/01/04/file.txt", 1));
+        metric.register(&object("other/file.txt", 5));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        assert_eq!(report.get("date_partitions", "total_series"), Some("1"));
+        assert_eq!(report.get("date_partitions", "undated_files"), Some("1"));
+        assert_eq!(
+            report.get("date_partitions", "series_1_first"),
+            Some("2018-01-01")
+        );
+        assert_eq!(
+            report.get("date_partitions", "series_1_missing_periods"),
+            Some("2")
+        );
+        assert_eq!(
+            report.get("date_partitions", "series_1_gap_1_from"),
+            Some("2018-01-05")
+        );
+        assert_eq!(
+            report.get("date_partitions", "series_1_gap_2_to"),
+            Some("2018-01-08")
+        );
+        assert_eq!(
+            report.get("date_partitions", "series_1_small_1_name"),
+            Some("2018-01-04")
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Encryption metrics tracking for S3 objects.
+use rusoto_s3::{HeadObjectOutput, Object};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Metric;
+use report::Report;
+
+/// Container struct for encryption metrics tracked by S3.
+pub struct Encryption {
+    unencrypted: (u64, u64),
+    sse_s3: (u64, u64),
+    sse_kms: (u64, u64),
+    sse_kms_dsse: (u64, u64),
+    other: (u64, u64),
+    failed: (u64, u64),
+    kms_keys: HashMap<String, u64>,
+}
+
+/// Main implementation.
+impl Encryption {
+    /// Constructs a new `Encryption` struct.
+    pub fn new() -> Encryption {
+        Encryption {
+            unencrypted: (0, 0),
+            sse_s3: (0, 0),
+            sse_kms: (0, 0),
+            sse_kms_dsse: (0, 0),
+            other: (0, 0),
+            failed: (0, 0),
+            kms_keys: HashMap::new(),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Encryption {
+    /// Constructs a new `Encryption` struct.
+    fn default() -> Encryption {
+        Encryption::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Encryption {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, _object: &Object) {}
+
+    /// Registers the headers of an S3 `Object` with this metric struct.
+    fn register_head(&mut self, object: &Object, head: &HeadObjectOutput) {
+        let counter = match head.server_side_encryption.as_deref() {
+            None => &mut self.unencrypted,
+            Some("AES256") => &mut self.sse_s3,
+            Some(algorithm @ "aws:kms") | Some(algorithm @ "aws:kms:dsse") => {
+                // track the key identifier, if provided
+                if let Some(ref key_id) = head.ssekms_key_id {
+                    *self.kms_keys.entry(key_id.to_string()).or_insert(0) += 1;
+                }
+                if algorithm == "aws:kms" {
+                    &mut self.sse_kms
+                } else {
+                    &mut self.sse_kms_dsse
+                }
+            }
+            Some(_) => &mut self.other,
+        };
+
+        counter.0 += 1;
+        counter.1 += super::get_size(object);
+    }
+
+    /// Registers an S3 `Object` whose headers could not be fetched.
+    ///
+    /// Objects encrypted with customer keys (SSE-C) always land here, as
+    /// `HeadObject` requests fail without the key used to encrypt them.
+    fn register_failed_head(&mut self, object: &Object) {
+        self.failed.0 += 1;
+        self.failed.1 += super::get_size(object);
+    }
+
+    /// Reports all internal statistics under the `encryption` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: encryption
+        report.head("encryption");
+
+        // log out the counts of each type of encryption
+        let counters = [
+            ("unencrypted", self.unencrypted),
+            ("sse_s3", self.sse_s3),
+            ("sse_kms", self.sse_kms),
+            ("sse_kms_dsse", self.sse_kms_dsse),
+            ("other", self.other),
+            ("failed", self.failed),
+        ];
+
+        for (label, (files, bytes)) in &counters {
+            report.pair(&format!("{}_files", label), files);
+            report.pair(&format!("{}_bytes", label), bytes);
+        }
+
+        // sort keys by their frequency, then by ID
+        let mut kms_keys: Vec<_> = self.kms_keys.iter().collect();
+        kms_keys.sort_by_key(|(id, files)| (Reverse(**files), *id));
+
+        // log out each KMS key, ranked by frequency
+        report.pair("unique_kms_keys", kms_keys.len());
+
+        for (idx, (key_id, files)) in kms_keys.into_iter().enumerate() {
+            let label = format!("kms_key_{}", idx + 1);
+
+            report.pair(&format!("{}_id", label), key_id);
+            report.pair(&format!("{}_files", label), files);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encryption;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::{HeadObjectOutput, Object};
+
+    fn head(algorithm: Option<&str>, key_id: Option<&str>) -> HeadObjectOutput {
+        HeadObjectOutput {
+            server_side_encryption: algorithm.map(|s| s.into()),
+            ssekms_key_id: key_id.map(|s| s.into()),
+            ..HeadObjectOutput::default()
+        }
+    }
+
+    #[test]
+    fn reporting_encryption() {
+        let mut metric = Encryption::new();
+        let object = Object {
+            key: Some("file.txt".into()),
+            size: Some(10),
+            ..Object::default()
+        };
+
+        metric.register_head(&object, &head(None, None));
+        metric.register_head(&object, &head(Some("AES256"), None));
+        metric.register_head(&object, &head(Some("aws:kms"), Some("key-1")));
+        metric.register_head(&object, &head(Some("aws:kms"), Some("key-1")));
+        metric.register_head(&object, &head(Some("aws:kms:dsse"), Some("key-2")));
+        metric.register_head(&object, &head(Some("aws:future"), None));
+        metric.register_failed_head(&object);
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("encryption", label);
+
+        assert_eq!(get("unencrypted_files"), Some("1"));
+        assert_eq!(get("sse_s3_files"), Some("1"));
+        assert_eq!(get("sse_kms_files"), Some("2"));
+        assert_eq!(get("sse_kms_bytes"), Some("20"));
+        assert_eq!(get("sse_kms_dsse_files"), Some("1"));
+        assert_eq!(get("other_files"), Some("1"));
+        assert_eq!(get("failed_files"), Some("1"));
+        assert_eq!(get("unique_kms_keys"), Some("2"));
+        assert_eq!(get("kms_key_1_id"), Some("key-1"));
+        assert_eq!(get("kms_key_1_files"), Some("2"));
+    }
+}
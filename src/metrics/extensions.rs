@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use super::Metric;
+use report::Report;
 
 /// Container struct for extension metrics tracked by S3.
 pub struct Extensions {
@@ -14,13 +15,21 @@ pub struct Extensions {
 /// Main implementation.
 impl Extensions {
     /// Constructs a new `Extensions` struct.
-    pub(super) fn new() -> Extensions {
+    pub fn new() -> Extensions {
         Extensions {
             extensions: HashMap::new(),
         }
     }
 }
 
+/// Default implementation.
+impl Default for Extensions {
+    /// Constructs a new `Extensions` struct.
+    fn default() -> Extensions {
+        Extensions::new()
+    }
+}
+
 /// Metric implementation.
 impl Metric for Extensions {
     /// Registers an S3 `Object` with this metric struct.
@@ -34,11 +43,11 @@ impl Metric for Extensions {
         }
     }
 
-    /// Prints out all internal statistics under the `extensions` header.
-    fn print(&self) {
+    /// Reports all internal statistics under the `extensions` header.
+    fn report(&self, report: &mut Report) {
         // next segment: extensions
-        ::util::log_head("extensions");
-        ::util::log_pair("unique_extensions", self.extensions.len());
+        report.head("extensions");
+        report.pair("unique_extensions", self.extensions.len());
 
         // find the most frequent extension
         let prevalent_extension = self
@@ -48,7 +57,7 @@ impl Metric for Extensions {
 
         // log out a potential most frequent
         if let Some((ext, _)) = prevalent_extension {
-            ::util::log_pair("most_popular_extension", ext);
+            report.pair("most_popular_extension", ext);
         }
     }
 }
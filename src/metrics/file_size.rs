@@ -3,6 +3,7 @@ use bounded::Bounded;
 use rusoto_s3::Object;
 
 use super::Metric;
+use report::Report;
 
 /// Container struct for file size metrics tracked by S3.
 pub struct FileSize {
@@ -15,7 +16,7 @@ pub struct FileSize {
 /// Main implementation.
 impl FileSize {
     /// Constructs a new `FileSize` struct.
-    pub(super) fn new() -> FileSize {
+    pub fn new() -> FileSize {
         FileSize {
             total_keys: 0,
             total_space: 0,
@@ -25,6 +26,14 @@ impl FileSize {
     }
 }
 
+/// Default implementation.
+impl Default for FileSize {
+    /// Constructs a new `FileSize` struct.
+    fn default() -> FileSize {
+        FileSize::new()
+    }
+}
+
 /// Metric implementation.
 impl Metric for FileSize {
     /// Registers an S3 `Object` with this metric struct.
@@ -45,8 +54,8 @@ impl Metric for FileSize {
         );
     }
 
-    /// Prints out all internal statistics under the `file_size` header.
-    fn print(&self) {
+    /// Reports all internal statistics under the `file_size` header.
+    fn report(&self, report: &mut Report) {
         // get average file size, protect against /0
         let average_file = match self.total_keys {
             0 => 0,
@@ -54,22 +63,22 @@ impl Metric for FileSize {
         };
 
         // next segment: file_size
-        ::util::log_head("file_size");
+        report.head("file_size");
 
         // log the average size as both readable and bytes
-        ::util::log_pair("average_file_size", ::util::convert_bytes(average_file));
-        ::util::log_pair("average_file_bytes", average_file);
+        report.pair("average_file_size", ::util::convert_bytes(average_file));
+        report.pair("average_file_bytes", average_file);
 
         // log out the bounds of the largest file
-        ::util::log_bound("largest_file", &self.largest_file, |size| {
-            ::util::log_pair("largest_file_size", ::util::convert_bytes(size));
-            ::util::log_pair("largest_file_bytes", size);
+        report.bound("largest_file", &self.largest_file, |report, size| {
+            report.pair("largest_file_size", ::util::convert_bytes(size));
+            report.pair("largest_file_bytes", size);
         });
 
         // log out the bounds of the smallest file
-        ::util::log_bound("smallest_file", &self.smallest_file, |size| {
-            ::util::log_pair("smallest_file_size", ::util::convert_bytes(size));
-            ::util::log_pair("smallest_file_bytes", size);
+        report.bound("smallest_file", &self.smallest_file, |report, size| {
+            report.pair("smallest_file_size", ::util::convert_bytes(size));
+            report.pair("smallest_file_bytes", size);
         });
     }
 }
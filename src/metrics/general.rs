@@ -6,6 +6,7 @@ use std::path::Path;
 use std::time::{Duration, SystemTime};
 
 use super::Metric;
+use report::Report;
 
 /// Container struct for general metrics tracked by S3.
 pub struct General {
@@ -19,7 +20,7 @@ pub struct General {
 /// Main implementation.
 impl General {
     /// Constructs a new `General` struct.
-    pub(super) fn new(prefix: &Option<String>) -> General {
+    pub fn new(prefix: &Option<String>) -> General {
         General {
             folder_set: HashSet::new(),
             nest_count: prefix
@@ -66,8 +67,8 @@ impl Metric for General {
         self.total_size += super::get_size(object);
     }
 
-    /// Prints out all internal statistics under the `general` header.
-    fn print(&self) {
+    /// Reports all internal statistics under the `general` header.
+    fn report(&self, report: &mut Report) {
         // task done, so check execution time
         let task_duration = Duration::from_secs(
             SystemTime::now()
@@ -77,12 +78,13 @@ impl Metric for General {
         );
 
         // initial header!
-        println!("[general]");
+        report.head("general");
 
         // log out the total time, total space, and total file count
-        ::util::log_pair("total_time", humantime::format_duration(task_duration));
-        ::util::log_pair("total_files", self.total_keys);
-        ::util::log_pair("total_folders", self.folder_set.len());
-        ::util::log_pair("total_storage", ::util::convert_bytes(self.total_size));
+        report.pair("total_time", humantime::format_duration(task_duration));
+        report.pair("total_files", self.total_keys);
+        report.pair("total_folders", self.folder_set.len());
+        report.pair("total_storage", ::util::convert_bytes(self.total_size));
+        report.pair("total_bytes", self.total_size);
     }
 }
@@ -0,0 +1,213 @@
+//! Custom grouping metrics tracking for S3 objects.
+use chrono::{DateTime, Utc};
+use config::Group;
+use dates::Formatter;
+use rusoto_s3::Object;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Metric;
+use report::Report;
+
+/// Container struct for custom grouping metrics, driven by configuration.
+///
+/// Each key is matched against the pattern of the group, and the captured
+/// value is used to aggregate statistics about all matching objects.
+pub struct GroupBy {
+    group: Group,
+    formatter: Formatter,
+    values: HashMap<String, Totals>,
+    unmatched: (u64, u64),
+}
+
+/// Totals struct to track the statistics of a single group value.
+#[derive(Debug, Default)]
+struct Totals {
+    files: u64,
+    bytes: u64,
+    largest: (String, u64),
+    latest: Option<(String, DateTime<Utc>)>,
+}
+
+/// Main implementation.
+impl GroupBy {
+    /// Constructs a new `GroupBy` struct.
+    pub fn new(group: Group, formatter: &Formatter) -> GroupBy {
+        GroupBy {
+            group,
+            formatter: formatter.clone(),
+            values: HashMap::new(),
+            unmatched: (0, 0),
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for GroupBy {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let size = super::get_size(object);
+
+        // track anything not matching the pattern separately
+        let value = match extract(&self.group, key) {
+            Some(value) => value,
+            None => {
+                self.unmatched.0 += 1;
+                self.unmatched.1 += size;
+                return;
+            }
+        };
+
+        let totals = self.values.entry(value).or_default();
+
+        totals.files += 1;
+        totals.bytes += size;
+
+        // track the largest object of the value
+        if totals.files == 1 || size > totals.largest.1 {
+            totals.largest = (key.to_string(), size);
+        }
+
+        // track the latest modification of the value
+        if let Some(modified) = super::get_modified(object) {
+            if totals.latest.as_ref().is_none_or(|(_, at)| modified > *at) {
+                totals.latest = Some((key.to_string(), modified));
+            }
+        }
+    }
+
+    /// Reports all internal statistics under the header of the group.
+    fn report(&self, report: &mut Report) {
+        // next segment: custom group
+        report.head(&self.group.name);
+
+        report.pair("total_values", self.values.len());
+        report.pair("unmatched_files", self.unmatched.0);
+        report.pair("unmatched_bytes", self.unmatched.1);
+
+        // rank values by bytes, then by name
+        let mut values: Vec<(&String, &Totals)> = self.values.iter().collect();
+        values.sort_by_key(|(value, totals)| (Reverse(totals.bytes), *value));
+
+        let limit = self.group.limit.unwrap_or(values.len());
+
+        // log out the statistics of each value
+        for (idx, (value, totals)) in values.iter().take(limit).enumerate() {
+            let label = format!("value_{}", idx + 1);
+
+            report.pair(&format!("{}_name", label), value);
+            report.pair(&format!("{}_files", label), totals.files);
+            report.pair(
+                &format!("{}_storage", label),
+                ::util::convert_bytes(totals.bytes),
+            );
+            report.pair(&format!("{}_bytes", label), totals.bytes);
+            report.pair(&format!("{}_largest_file_name", label), &totals.largest.0);
+            report.pair(&format!("{}_largest_file_bytes", label), totals.largest.1);
+
+            if let Some((ref key, ref modified)) = totals.latest {
+                report.pair(&format!("{}_latest_file_name", label), key);
+                report.pair(
+                    &format!("{}_latest_file_date", label),
+                    self.formatter.format(modified),
+                );
+            }
+        }
+    }
+}
+
+/// Extracts the value of a group from an object key.
+///
+/// All participating captures are joined using `/`, falling back to the
+/// entire match when the pattern contains no capture groups.
+fn extract(group: &Group, key: &str) -> Option<String> {
+    let captures = group.pattern.captures(key)?;
+
+    // skip the implicit group of the entire match
+    let values: Vec<&str> = captures
+        .iter()
+        .skip(1)
+        .flatten()
+        .map(|capture| capture.as_str())
+        .collect();
+
+    if values.is_empty() {
+        return captures.get(0).map(|whole| whole.as_str().to_string());
+    }
+
+    Some(values.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GroupBy;
+    use config::Group;
+    use dates::Formatter;
+    use metrics::Metric;
+    use regex::Regex;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn group(pattern: &str) -> Group {
+        Group {
+            name: "tenants".into(),
+            pattern: Regex::new(pattern).unwrap(),
+            limit: None,
+        }
+    }
+
+    fn object(key: &str, size: i64, modified: &str) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            last_modified: Some(modified.into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn extracting_group_values() {
+        let tenants = group("^tenants/(?P<tenant>[^/]+)/");
+        let dates = group("/(\\d{4})/(\\d{2})/");
+        let whole = group("\\.[a-z]+$");
+
+        assert_eq!(
+            super::extract(&tenants, "tenants/acme/file.txt"),
+            Some("acme".into())
+        );
+        assert_eq!(super::extract(&tenants, "other/file.txt"), None);
+        assert_eq!(
+            super::extract(&dates, "logs/2018/01/file.txt"),
+            Some("2018/01".into())
+        );
+        assert_eq!(super::extract(&whole, "file.txt"), Some(".txt".into()));
+    }
+
+    #[test]
+    fn reporting_group_values() {
+        let mut metric = GroupBy::new(group("^tenants/(?P<tenant>[^/]+)/"), &Formatter::default());
+
+        metric.register(&object("tenants/a/1.txt", 10, "2018-01-01T00:00:00.000Z"));
+        metric.register(&object("tenants/a/2.txt", 30, "2018-01-02T00:00:00.000Z"));
+        metric.register(&object("tenants/b/1.txt", 20, "2018-01-03T00:00:00.000Z"));
+        metric.register(&object("other.txt", 5, "2018-01-01T00:00:00.000Z"));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        assert_eq!(report.get("tenants", "total_values"), Some("2"));
+        assert_eq!(report.get("tenants", "unmatched_files"), Some("1"));
+        assert_eq!(report.get("tenants", "value_1_name"), Some("a"));
+        assert_eq!(report.get("tenants", "value_1_bytes"), Some("40"));
+        assert_eq!(
+            report.get("tenants", "value_1_largest_file_name"),
+            Some("tenants/a/2.txt")
+        );
+        assert_eq!(
+            report.get("tenants", "value_1_latest_file_name"),
+            Some("tenants/a/2.txt")
+        );
+        assert_eq!(report.get("tenants", "value_2_name"), Some("b"));
+    }
+}
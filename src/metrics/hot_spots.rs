@@ -0,0 +1,278 @@
+//! Hot spot metrics tracking for S3 objects.
+use rusoto_s3::Object;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Metric;
+use report::Report;
+
+/// The number of entries to include in each ranked list.
+const LIMIT: usize = 10;
+
+/// The `GET` requests per second S3 supports for each prefix partition.
+const GET_RATE: u64 = 5500;
+
+/// The `PUT` requests per second S3 supports for each prefix partition.
+const PUT_RATE: u64 = 3500;
+
+/// Container struct for prefix hot spot metrics.
+///
+/// S3 scales request rates per partition of the key space, so keys which
+/// are concentrated under a few leading characters or prefixes (or which
+/// always increase, such as timestamps) limit the rates a bucket reaches.
+/// Keys are analysed relative to the prefix being scanned.
+///
+/// The partitions S3 creates aren't visible, so request rates are only a
+/// heuristic which treats each top level prefix as its own partition.
+pub struct HotSpots {
+    prefix_len: usize,
+    total_keys: u64,
+    leading_chars: HashMap<char, u64>,
+    prefixes: HashMap<String, u64>,
+    timestamp_leading: u64,
+    numeric_leading: u64,
+    sequential: u64,
+    previous: Option<u64>,
+    previous_prefix: String,
+}
+
+/// Main implementation.
+impl HotSpots {
+    /// Constructs a new `HotSpots` struct.
+    pub fn new(prefix: &Option<String>) -> HotSpots {
+        HotSpots {
+            prefix_len: prefix.as_ref().map(String::len).unwrap_or(0),
+            total_keys: 0,
+            leading_chars: HashMap::new(),
+            prefixes: HashMap::new(),
+            timestamp_leading: 0,
+            numeric_leading: 0,
+            sequential: 0,
+            previous: None,
+            previous_prefix: String::new(),
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for HotSpots {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+
+        // analyse keys relative to the scanned prefix
+        let relative = key.get(self.prefix_len..).unwrap_or(key);
+
+        self.total_keys += 1;
+
+        if let Some(leading) = relative.chars().next() {
+            *self.leading_chars.entry(leading).or_insert(0) += 1;
+        }
+
+        // keys at the root share a single prefix
+        let prefix = match relative.find('/') {
+            Some(idx) => &relative[..=idx],
+            None => ::util::ROOT,
+        };
+
+        *self.prefixes.entry(prefix.to_string()).or_insert(0) += 1;
+
+        // detect keys beginning with timestamps or numbers
+        if is_timestamp(relative) {
+            self.timestamp_leading += 1;
+        }
+
+        let digits = relative.len()
+            - relative
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let number = relative[..digits].parse::<u64>().ok();
+
+        if digits > 0 {
+            self.numeric_leading += 1;
+        }
+
+        // sequences are only followed within a prefix, as sampled
+        // scans visit prefixes in a random order
+        if self.previous_prefix != prefix {
+            self.previous_prefix = prefix.to_string();
+            self.previous = None;
+        }
+
+        // numbers following on from the previous key are sequential
+        if let (Some(number), Some(previous)) = (number, self.previous) {
+            if previous.checked_add(1) == Some(number) {
+                self.sequential += 1;
+            }
+        }
+
+        self.previous = number;
+    }
+
+    /// Reports all internal statistics under the `hot_spots` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: hot_spots
+        report.head("hot_spots");
+        report.pair("total_files", self.total_keys);
+
+        // log out the distribution of leading characters
+        let leading: HashMap<String, u64> = self
+            .leading_chars
+            .iter()
+            .map(|(c, count)| (c.to_string(), *count))
+            .collect();
+
+        report.pair("unique_leading_chars", leading.len());
+        self.report_spread(report, "leading_char", &leading);
+
+        // log out the distribution of top level prefixes
+        report.pair("unique_prefixes", self.prefixes.len());
+        self.report_spread(report, "prefix", &self.prefixes);
+
+        // estimate the sustainable rates, assuming requests follow keys
+        // and that each top level prefix is a partition (a heuristic)
+        let hottest = self.prefixes.values().max().cloned().unwrap_or(0);
+        let estimate = |rate: u64| match hottest {
+            0 => 0,
+            v => (rate as f64 * self.total_keys as f64 / v as f64).round() as u64,
+        };
+
+        report.pair("heuristic_get_rate", estimate(GET_RATE));
+        report.pair("heuristic_put_rate", estimate(PUT_RATE));
+
+        // log out any problematic key schemes
+        report.pair("timestamp_leading_files", self.timestamp_leading);
+        report.pair("numeric_leading_files", self.numeric_leading);
+        report.pair("sequential_files", self.sequential);
+        report.pair(
+            "timestamp_leading_scheme",
+            self.is_majority(self.timestamp_leading),
+        );
+        report.pair("sequential_scheme", self.is_majority(self.sequential));
+    }
+}
+
+/// Private implementation.
+impl HotSpots {
+    /// Reports the spread of keys across groups, ranked by size.
+    ///
+    /// The imbalance is the ratio of the largest group against the mean,
+    /// where `1.00` means that keys are evenly spread.
+    fn report_spread(&self, report: &mut Report, label: &str, groups: &HashMap<String, u64>) {
+        let mut ranked: Vec<_> = groups.iter().collect();
+        ranked.sort_by_key(|(name, count)| (Reverse(**count), *name));
+
+        let imbalance = match ranked.first() {
+            Some((_, max)) => **max as f64 / (self.total_keys as f64 / groups.len() as f64),
+            None => 0.0,
+        };
+
+        report.pair(&format!("{}_imbalance", label), format!("{:.2}", imbalance));
+
+        for (idx, (name, count)) in ranked.iter().take(LIMIT).enumerate() {
+            let entry = format!("{}_{}", label, idx + 1);
+
+            report.pair(&format!("{}_name", entry), name);
+            report.pair(&format!("{}_files", entry), count);
+            report.pair(
+                &format!("{}_percent", entry),
+                format!("{:.2}", **count as f64 / self.total_keys as f64 * 100.0),
+            );
+        }
+    }
+
+    /// Determines whether a count is the majority of all keys.
+    fn is_majority(&self, count: u64) -> bool {
+        count * 2 > self.total_keys
+    }
+}
+
+/// Determines whether a key begins with a timestamp.
+///
+/// Dates (`yyyy-mm-dd`, `yyyy/mm/dd`, `yyyymmdd`) and epoch timestamps in
+/// seconds or milliseconds are all recognised.
+fn is_timestamp(key: &str) -> bool {
+    let bytes = key.as_bytes();
+    let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+
+    // separated dates, e.g. 2018-01-01 or 2018/01/01
+    if digits == 4 && bytes.len() >= 10 {
+        let separated = (bytes[4] == b'-' || bytes[4] == b'/')
+            && bytes[7] == bytes[4]
+            && [5, 6, 8, 9].iter().all(|idx| bytes[*idx].is_ascii_digit());
+
+        return separated && is_year(&key[..4]);
+    }
+
+    // compact dates, and epochs between 2001 and 2286
+    match digits {
+        8 | 14 => is_year(&key[..4]),
+        10 | 13 => key.starts_with(|c| c != '0'),
+        _ => false,
+    }
+}
+
+/// Determines whether a string is a plausible year.
+fn is_year(year: &str) -> bool {
+    year.parse::<u32>()
+        .map(|year| (1970..2100).contains(&year))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HotSpots;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(1),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn detecting_timestamps() {
+        assert!(super::is_timestamp("2018-01-01T00:00:00Z.log"));
+        assert!(super::is_timestamp("2018/01/01/file.txt"));
+        assert!(super::is_timestamp("20180101-file.txt"));
+        assert!(super::is_timestamp("1514764800.json"));
+        assert!(super::is_timestamp("1514764800000.json"));
+        assert!(!super::is_timestamp("0000000001.json"));
+        assert!(!super::is_timestamp("1234-file.txt"));
+        assert!(!super::is_timestamp("logs/file.txt"));
+    }
+
+    #[test]
+    fn reporting_hot_spots() {
+        let mut metric = HotSpots::new(&Some("data/".into()));
+
+        for idx in 1..=6 {
+            metric.register(&object(&format!("data/{:04}.json", idx)));
+        }
+
+        metric.register(&object("data/logs/a.txt"));
+        metric.register(&object("data/logs/b.txt"));
+        metric.register(&object("data/0007/c.txt"));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("hot_spots", label);
+
+        assert_eq!(get("unique_leading_chars"), Some("2"));
+        assert_eq!(get("leading_char_1_name"), Some("0"));
+        assert_eq!(get("leading_char_1_percent"), Some("77.78"));
+        assert_eq!(get("leading_char_imbalance"), Some("1.56"));
+        assert_eq!(get("prefix_1_name"), Some("<root>"));
+        assert_eq!(get("prefix_2_name"), Some("logs/"));
+        assert_eq!(get("heuristic_get_rate"), Some("8250"));
+        assert_eq!(get("sequential_files"), Some("5"));
+        assert_eq!(get("sequential_scheme"), Some("true"));
+        assert_eq!(get("timestamp_leading_scheme"), Some("false"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Key naming hygiene metrics tracking for S3 objects.
+use rusoto_s3::Object;
+
+use super::Metric;
+use report::Report;
+
+/// The number of example keys to keep for each issue.
+const EXAMPLES: usize = 3;
+
+/// Characters which AWS recommends avoiding in object keys.
+const AVOIDED: &[char] = &[
+    '\\', '{', '}', '^', '%', '`', '[', ']', '"', '<', '>', '~', '#', '|',
+];
+
+/// Check to determine whether a key has an issue.
+type Check = fn(&str) -> bool;
+
+/// Checks applied to every key, along with the name of the issue.
+const CHECKS: &[(&str, Check)] = &[
+    ("leading_whitespace", |key| {
+        segments(key).any(|s| s.starts_with(char::is_whitespace))
+    }),
+    ("trailing_whitespace", |key| {
+        segments(key).any(|s| s.ends_with(char::is_whitespace))
+    }),
+    ("control_characters", |key| {
+        key.chars().any(char::is_control)
+    }),
+    // only seen as the replacement character left by lossy decoding
+    ("invalid_utf8", |key| key.contains('\u{FFFD}')),
+    ("non_ascii", |key| !key.is_ascii()),
+    ("leading_slash", |key| key.starts_with('/')),
+    ("double_slash", |key| key.contains("//")),
+    ("relative_segments", |key| {
+        segments(key).any(|s| s == "." || s == "..")
+    }),
+    ("avoided_characters", |key| key.contains(AVOIDED)),
+];
+
+/// Container struct for key naming hygiene metrics.
+///
+/// Each key is run through a set of checks for names which are known to
+/// cause problems for tools (and file systems) consuming the bucket.
+///
+/// Keys reach metrics as strings, so invalid UTF-8 can only be detected by
+/// the `U+FFFD` replacement character left behind when inventory rows and
+/// local paths are decoded. Listings from S3 are always valid UTF-8, and any
+/// key which genuinely contains `U+FFFD` is flagged as well.
+pub struct Hygiene {
+    flagged: u64,
+    issues: Vec<(u64, Vec<String>)>,
+}
+
+/// Main implementation.
+impl Hygiene {
+    /// Constructs a new `Hygiene` struct.
+    pub fn new() -> Hygiene {
+        Hygiene {
+            flagged: 0,
+            issues: vec![(0, Vec::new()); CHECKS.len()],
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Hygiene {
+    /// Constructs a new `Hygiene` struct.
+    fn default() -> Hygiene {
+        Hygiene::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Hygiene {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let mut flagged = false;
+
+        for ((_, check), (count, examples)) in CHECKS.iter().zip(&mut self.issues) {
+            if !check(key) {
+                continue;
+            }
+
+            flagged = true;
+            *count += 1;
+
+            // keep a few examples of each issue
+            if examples.len() < EXAMPLES {
+                examples.push(escape(key));
+            }
+        }
+
+        if flagged {
+            self.flagged += 1;
+        }
+    }
+
+    /// Reports all internal statistics under the `hygiene` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: hygiene
+        report.head("hygiene");
+        report.pair("flagged_files", self.flagged);
+
+        // log out the count and examples of each issue
+        for ((name, _), (count, examples)) in CHECKS.iter().zip(&self.issues) {
+            report.pair(&format!("{}_files", name), count);
+
+            for (idx, example) in examples.iter().enumerate() {
+                report.pair(&format!("{}_example_{}", name, idx + 1), example);
+            }
+        }
+    }
+}
+
+/// Retrieves all `/` separated segments of a key.
+fn segments(key: &str) -> impl Iterator<Item = &str> {
+    key.split('/')
+}
+
+/// Escapes any control characters in a key, to keep output on one line.
+fn escape(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            c if c.is_control() => c.escape_default().to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hygiene;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(1),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn flagging_problematic_keys() {
+        let mut metric = Hygiene::new();
+
+        for key in &[
+            "logs/file.txt",
+            "logs /file.txt",
+            " file.txt",
+            "logs/file\n.txt",
+            "logs/caf\u{e9}.txt",
+            "/logs/file.txt",
+            "logs//file.txt",
+            "logs/../file.txt",
+            "logs/file#1.txt",
+        ] {
+            metric.register(&object(key));
+        }
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("hygiene", label);
+
+        assert_eq!(get("flagged_files"), Some("8"));
+        assert_eq!(get("leading_whitespace_files"), Some("1"));
+        assert_eq!(get("trailing_whitespace_files"), Some("1"));
+        assert_eq!(get("control_characters_files"), Some("1"));
+        assert_eq!(
+            get("control_characters_example_1"),
+            Some("logs/file\\n.txt")
+        );
+        assert_eq!(get("invalid_utf8_files"), Some("0"));
+        assert_eq!(get("non_ascii_files"), Some("1"));
+        assert_eq!(get("leading_slash_files"), Some("1"));
+        assert_eq!(get("double_slash_files"), Some("1"));
+        assert_eq!(get("relative_segments_files"), Some("1"));
+        assert_eq!(get("avoided_characters_example_1"), Some("logs/file#1.txt"));
+    }
+}
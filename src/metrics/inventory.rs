@@ -0,0 +1,189 @@
+//! Inventory metrics tracking for S3 objects.
+use chrono::{DateTime, Utc};
+use inventory::Record;
+use rusoto_s3::Object;
+use std::collections::BTreeMap;
+
+use super::Metric;
+use report::Report;
+
+/// Container struct for inventory metrics tracked by S3.
+///
+/// These metrics are only available when reading from an inventory
+/// report, as listings do not include any of the tracked columns.
+pub struct Inventory {
+    reference: DateTime<Utc>,
+    storage_classes: BTreeMap<String, (u64, u64)>,
+    encryption: BTreeMap<String, (u64, u64)>,
+    replication: BTreeMap<String, (u64, u64)>,
+    lock_modes: BTreeMap<String, (u64, u64)>,
+    legal_holds: BTreeMap<String, (u64, u64)>,
+    access_tiers: BTreeMap<String, (u64, u64)>,
+    retained: (u64, u64),
+}
+
+/// Main implementation.
+impl Inventory {
+    /// Constructs a new `Inventory` struct.
+    pub fn new(reference: DateTime<Utc>) -> Inventory {
+        Inventory {
+            reference,
+            storage_classes: BTreeMap::new(),
+            encryption: BTreeMap::new(),
+            replication: BTreeMap::new(),
+            lock_modes: BTreeMap::new(),
+            legal_holds: BTreeMap::new(),
+            access_tiers: BTreeMap::new(),
+            retained: (0, 0),
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for Inventory {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, _object: &Object) {}
+
+    /// Registers the inventory record of an S3 `Object` with this metric struct.
+    fn register_inventory(&mut self, object: &Object, record: &Record) {
+        let size = super::get_size(object);
+
+        // track each of the provided columns
+        let counters = [
+            (&mut self.storage_classes, &object.storage_class),
+            (&mut self.encryption, &record.encryption_status),
+            (&mut self.replication, &record.replication_status),
+            (&mut self.lock_modes, &record.object_lock_mode),
+            (&mut self.legal_holds, &record.object_lock_legal_hold),
+            (&mut self.access_tiers, &record.access_tier),
+        ];
+
+        for (counter, value) in counters {
+            if let Some(value) = value {
+                let entry = counter.entry(label(value)).or_insert((0, 0));
+
+                entry.0 += 1;
+                entry.1 += size;
+            }
+        }
+
+        // track objects still retained by a lock at the reference time
+        let retained = record
+            .object_lock_retain_until
+            .as_ref()
+            .and_then(|date| ::dates::parse(date))
+            .is_some_and(|date| date > self.reference);
+
+        if retained {
+            self.retained.0 += 1;
+            self.retained.1 += size;
+        }
+    }
+
+    /// Reports all internal statistics under the `inventory` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: inventory
+        report.head("inventory");
+
+        // log out the counts of each value of each column
+        let counters = [
+            ("storage_class", &self.storage_classes),
+            ("encryption", &self.encryption),
+            ("replication", &self.replication),
+            ("object_lock", &self.lock_modes),
+            ("legal_hold", &self.legal_holds),
+            ("access_tier", &self.access_tiers),
+        ];
+
+        for (prefix, counter) in &counters {
+            for (value, (files, bytes)) in counter.iter() {
+                report.pair(&format!("{}_{}_files", prefix, value), files);
+                report.pair(&format!("{}_{}_bytes", prefix, value), bytes);
+            }
+        }
+
+        // log out the objects under retention
+        report.pair("retained_files", self.retained.0);
+        report.pair("retained_bytes", self.retained.1);
+    }
+}
+
+/// Converts a column value into a label suitable for output keys.
+fn label(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Inventory;
+    use chrono::{TimeZone, Utc};
+    use inventory::Record;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(size: i64, storage_class: &str) -> Object {
+        Object {
+            key: Some("file.txt".into()),
+            size: Some(size),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            storage_class: Some(storage_class.into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn converting_column_labels() {
+        assert_eq!(super::label("SSE-KMS"), "sse_kms");
+        assert_eq!(super::label("ARCHIVE_ACCESS"), "archive_access");
+    }
+
+    #[test]
+    fn reporting_inventory_columns() {
+        let reference = Utc.with_ymd_and_hms(2018, 6, 1, 0, 0, 0).unwrap();
+        let mut metric = Inventory::new(reference);
+
+        let locked = |until: &str| Record {
+            encryption_status: Some("SSE-KMS".into()),
+            object_lock_mode: Some("GOVERNANCE".into()),
+            object_lock_retain_until: Some(until.into()),
+            object_lock_legal_hold: Some("OFF".into()),
+            ..Record::default()
+        };
+
+        metric.register_inventory(&object(1, "STANDARD"), &locked("2019-01-01T00:00:00Z"));
+        metric.register_inventory(&object(2, "STANDARD"), &locked("2018-01-01T00:00:00Z"));
+        metric.register_inventory(
+            &object(4, "INTELLIGENT_TIERING"),
+            &Record {
+                encryption_status: Some("SSE-S3".into()),
+                object_lock_legal_hold: Some("ON".into()),
+                access_tier: Some("ARCHIVE_ACCESS".into()),
+                ..Record::default()
+            },
+        );
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("inventory", label);
+
+        assert_eq!(get("storage_class_standard_files"), Some("2"));
+        assert_eq!(get("storage_class_intelligent_tiering_bytes"), Some("4"));
+        assert_eq!(get("encryption_sse_kms_files"), Some("2"));
+        assert_eq!(get("encryption_sse_s3_files"), Some("1"));
+        assert_eq!(get("object_lock_governance_bytes"), Some("3"));
+        assert_eq!(get("legal_hold_off_files"), Some("2"));
+        assert_eq!(get("legal_hold_on_files"), Some("1"));
+        assert_eq!(get("access_tier_archive_access_files"), Some("1"));
+        assert_eq!(get("replication_completed_files"), None);
+        assert_eq!(get("retained_files"), Some("1"));
+        assert_eq!(get("retained_bytes"), Some("1"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Key metrics tracking for S3 objects.
+use bounded::Bounded;
+use rusoto_s3::Object;
+use std::collections::BTreeMap;
+
+use super::Metric;
+use report::Report;
+
+/// The maximum length of an S3 key, in bytes.
+const KEY_LIMIT: usize = 1024;
+
+/// The distance from the limit at which a key is considered near it.
+const NEAR_LIMIT: usize = 64;
+
+/// The upper bounds of each key length bucket, in bytes.
+const LENGTHS: [usize; 4] = [64, 128, 256, 512];
+
+/// Container struct for key length and depth metrics.
+pub struct Keys {
+    total_keys: u64,
+    total_length: u64,
+    total_depth: u64,
+    longest_key: Bounded<usize>,
+    shortest_key: Bounded<usize>,
+    lengths: [u64; LENGTHS.len() + 1],
+    depths: BTreeMap<usize, u64>,
+    near_limit: u64,
+}
+
+/// Main implementation.
+impl Keys {
+    /// Constructs a new `Keys` struct.
+    pub fn new() -> Keys {
+        Keys {
+            total_keys: 0,
+            total_length: 0,
+            total_depth: 0,
+            longest_key: Bounded::new(0),
+            shortest_key: Bounded::new(0),
+            lengths: [0; LENGTHS.len() + 1],
+            depths: BTreeMap::new(),
+            near_limit: 0,
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Keys {
+    /// Constructs a new `Keys` struct.
+    fn default() -> Keys {
+        Keys::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Keys {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+
+        // limits apply to the encoded length, not the characters
+        let length = key.len();
+        let depth = key.matches('/').count();
+
+        self.total_keys += 1;
+        self.total_length += length as u64;
+        self.total_depth += depth as u64;
+
+        // bucket the length into the histogram
+        let bucket = LENGTHS
+            .iter()
+            .position(|bound| length < *bound)
+            .unwrap_or(LENGTHS.len());
+
+        self.lengths[bucket] += 1;
+
+        // track anything getting close to the limit
+        if length + NEAR_LIMIT > KEY_LIMIT {
+            self.near_limit += 1;
+        }
+
+        *self.depths.entry(depth).or_insert(0) += 1;
+
+        // apply bounded updates
+        ::bounded::apply(&mut self.shortest_key, &mut self.longest_key, key, &length);
+    }
+
+    /// Reports all internal statistics under the `keys` header.
+    fn report(&self, report: &mut Report) {
+        // get averages, protect against /0
+        let average = |total: u64| match self.total_keys {
+            0 => 0.0,
+            v => total as f64 / v as f64,
+        };
+
+        // next segment: keys
+        report.head("keys");
+
+        report.pair(
+            "average_key_length",
+            format!("{:.2}", average(self.total_length)),
+        );
+
+        // log out the bounds of the longest key
+        report.bound("longest_key", &self.longest_key, |report, length| {
+            report.pair("longest_key_length", length);
+        });
+
+        // log out the bounds of the shortest key
+        report.bound("shortest_key", &self.shortest_key, |report, length| {
+            report.pair("shortest_key_length", length);
+        });
+
+        // log out the histogram of key lengths
+        for (idx, count) in self.lengths.iter().enumerate() {
+            match LENGTHS.get(idx) {
+                Some(bound) => report.pair(&format!("length_under_{}_files", bound), count),
+                None => report.pair(
+                    &format!("length_{}_plus_files", LENGTHS[LENGTHS.len() - 1]),
+                    count,
+                ),
+            }
+        }
+
+        report.pair("near_limit_files", self.near_limit);
+
+        // log out the distribution of key depths
+        report.pair("average_depth", format!("{:.2}", average(self.total_depth)));
+        report.pair(
+            "max_depth",
+            self.depths.keys().next_back().cloned().unwrap_or(0),
+        );
+
+        for (depth, count) in &self.depths {
+            report.pair(&format!("depth_{}_files", depth), count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Keys;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(1),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn reporting_key_lengths_and_depths() {
+        let mut metric = Keys::new();
+
+        metric.register(&object("file.txt"));
+        metric.register(&object("logs/file.txt"));
+        metric.register(&object(&format!("logs/{}", "a".repeat(1000))));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        assert_eq!(report.get("keys", "shortest_key_length"), Some("8"));
+        assert_eq!(report.get("keys", "shortest_key_name"), Some("file.txt"));
+        assert_eq!(report.get("keys", "longest_key_length"), Some("1005"));
+        assert_eq!(report.get("keys", "length_under_64_files"), Some("2"));
+        assert_eq!(report.get("keys", "length_512_plus_files"), Some("1"));
+        assert_eq!(report.get("keys", "near_limit_files"), Some("1"));
+        assert_eq!(report.get("keys", "max_depth"), Some("1"));
+        assert_eq!(report.get("keys", "depth_0_files"), Some("1"));
+        assert_eq!(report.get("keys", "depth_1_files"), Some("2"));
+    }
+}
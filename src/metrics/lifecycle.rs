@@ -0,0 +1,245 @@
+//! Lifecycle simulation metrics tracking for S3 objects.
+use chrono::{DateTime, Utc};
+use lifecycle::{self, Outcome, Rule};
+use rusoto_s3::Object;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use super::Metric;
+use report::Report;
+
+/// Number of bytes in a GB, as used by S3 pricing.
+const GIGABYTE: f64 = 1_073_741_824.0;
+
+/// Container struct for lifecycle simulation metrics tracked by S3.
+pub struct Lifecycle {
+    rules: Vec<Rule>,
+    reference: DateTime<Utc>,
+    cost_delta: f64,
+    expired: (u64, u64),
+    transitions: BTreeMap<String, (u64, u64)>,
+    rule_matches: BTreeMap<String, u64>,
+}
+
+/// Main implementation.
+impl Lifecycle {
+    /// Constructs a new `Lifecycle` struct.
+    pub fn new(rules: Vec<Rule>, reference: DateTime<Utc>) -> Lifecycle {
+        Lifecycle {
+            rules,
+            reference,
+            cost_delta: 0.0,
+            expired: (0, 0),
+            transitions: BTreeMap::new(),
+            rule_matches: BTreeMap::new(),
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for Lifecycle {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        // unparseable dates are reported by `Modification`
+        let modified = match super::get_modified(object) {
+            Some(modified) => modified,
+            None => return,
+        };
+
+        // pull various metadata
+        let key = super::get_key(object);
+        let size = super::get_size(object);
+        let current = object
+            .storage_class
+            .as_ref()
+            .map_or("STANDARD", |s| s.as_str());
+        let current_price = lifecycle::price(current).unwrap_or(0.0);
+
+        // evaluate all rules, tracking the outcome to apply (and its rule)
+        let mut outcome: Option<(Outcome, usize)> = None;
+
+        for (idx, rule) in self.rules.iter().enumerate() {
+            if !rule.applies(key, size) {
+                continue;
+            }
+
+            let result = match rule.evaluate(&modified, &self.reference) {
+                Some(result) => result,
+                None => continue,
+            };
+
+            // expiration wins, otherwise the cheapest transition
+            outcome = match (outcome, result) {
+                (Some((Outcome::Expire, winner)), _) => Some((Outcome::Expire, winner)),
+                (_, Outcome::Expire) => Some((Outcome::Expire, idx)),
+                (Some((Outcome::Transition(left), winner)), Outcome::Transition(right)) => {
+                    match lifecycle::compare_prices(right, left) {
+                        Ordering::Less => Some((Outcome::Transition(right), idx)),
+                        _ => Some((Outcome::Transition(left), winner)),
+                    }
+                }
+                (None, result) => Some((result, idx)),
+            };
+        }
+
+        // nothing to apply when no rule is due
+        let (outcome, winner) = match outcome {
+            Some(outcome) => outcome,
+            None => return,
+        };
+
+        // apply the outcome to the totals
+        match outcome {
+            Outcome::Expire => {
+                self.expired.0 += 1;
+                self.expired.1 += size;
+                self.cost_delta -= current_price * size as f64 / GIGABYTE;
+            }
+            Outcome::Transition(class) => {
+                let target_price = match lifecycle::price(class) {
+                    Some(price) => price,
+                    None => return,
+                };
+
+                // objects never transition back up to a pricier class
+                if class == current || target_price >= current_price {
+                    return;
+                }
+
+                let entry = self.transitions.entry(class.to_string()).or_insert((0, 0));
+
+                entry.0 += 1;
+                entry.1 += size;
+
+                self.cost_delta += (target_price - current_price) * size as f64 / GIGABYTE;
+            }
+        }
+
+        // count the rule which acted, using the rule index when no ID exists
+        let label = self.rules[winner]
+            .id
+            .clone()
+            .unwrap_or_else(|| winner.to_string());
+
+        *self.rule_matches.entry(label).or_insert(0) += 1;
+    }
+
+    /// Reports all internal statistics under the `lifecycle` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: lifecycle
+        report.head("lifecycle");
+
+        // log out the rules which could and could not be simulated
+        let simulated = self.rules.iter().filter(|rule| rule.is_simulated());
+        let skipped = self.rules.iter().filter(|rule| rule.tagged);
+
+        report.pair("simulated_rules", simulated.count());
+        report.pair("skipped_tagged_rules", skipped.count());
+
+        // log out the objects which would expire
+        report.pair("expired_files", self.expired.0);
+        report.pair("expired_size", ::util::convert_bytes(self.expired.1));
+        report.pair("expired_bytes", self.expired.1);
+
+        // log out the objects which would transition to each class
+        for (class, (files, bytes)) in &self.transitions {
+            let class = class.to_lowercase();
+
+            report.pair(&format!("transition_{}_files", class), files);
+            report.pair(
+                &format!("transition_{}_size", class),
+                ::util::convert_bytes(*bytes),
+            );
+            report.pair(&format!("transition_{}_bytes", class), bytes);
+        }
+
+        // log out the number of objects each rule acted upon
+        for (rule, files) in &self.rule_matches {
+            report.pair(&format!("rule_{}_files", rule), files);
+        }
+
+        // log out the projected change in monthly storage cost
+        report.pair("monthly_cost_delta_usd", format!("{:.2}", self.cost_delta));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lifecycle;
+    use chrono::{TimeZone, Utc};
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    const RULES: &str = r#"{
+        "Rules": [
+            {
+                "ID": "expire-tmp",
+                "Filter": { "Prefix": "tmp/" },
+                "Status": "Enabled",
+                "Expiration": { "Days": 1 }
+            },
+            {
+                "ID": "disabled",
+                "Status": "Disabled",
+                "Expiration": { "Days": 1 }
+            },
+            {
+                "ID": "tagged",
+                "Filter": { "Tag": { "Key": "tier", "Value": "cold" } },
+                "Status": "Enabled",
+                "Expiration": { "Days": 1 }
+            },
+            {
+                "ID": "archive-large",
+                "Filter": { "And": { "Prefix": "data/", "ObjectSizeGreaterThan": 1024 } },
+                "Status": "Enabled",
+                "Transitions": [{ "Days": 30, "StorageClass": "GLACIER" }]
+            },
+            {
+                "ID": "archive-all",
+                "Status": "Enabled",
+                "Transitions": [{ "Days": 30, "StorageClass": "STANDARD_IA" }]
+            }
+        ]
+    }"#;
+
+    fn object(key: &str, size: i64) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            storage_class: Some("STANDARD".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn reporting_lifecycle_simulations() {
+        let rules = ::lifecycle::parse(RULES).unwrap();
+        let reference = Utc.with_ymd_and_hms(2018, 6, 1, 0, 0, 0).unwrap();
+        let mut metric = Lifecycle::new(rules, reference);
+
+        metric.register(&object("tmp/file.txt", 100));
+        metric.register(&object("data/small.bin", 10));
+        metric.register(&object("data/large.bin", 2048));
+        metric.register(&object("other.txt", 100));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("lifecycle", label);
+
+        assert_eq!(get("simulated_rules"), Some("3"));
+        assert_eq!(get("skipped_tagged_rules"), Some("1"));
+        assert_eq!(get("expired_files"), Some("1"));
+        assert_eq!(get("expired_bytes"), Some("100"));
+        assert_eq!(get("transition_glacier_files"), Some("1"));
+        assert_eq!(get("transition_glacier_bytes"), Some("2048"));
+        assert_eq!(get("transition_standard_ia_files"), Some("2"));
+        assert_eq!(get("rule_expire-tmp_files"), Some("1"));
+        assert_eq!(get("rule_archive-large_files"), Some("1"));
+        assert_eq!(get("rule_archive-all_files"), Some("2"));
+        assert_eq!(get("rule_disabled_files"), None);
+    }
+}
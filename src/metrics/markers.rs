@@ -0,0 +1,216 @@
+//! Folder marker metrics tracking for S3 objects.
+use rusoto_s3::Object;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::Metric;
+use report::Report;
+
+/// The number of empty folders to list.
+const LIMIT: usize = 10;
+
+/// The number of marker names to keep as candidate empty folders.
+const CANDIDATES: usize = LIMIT * 10;
+
+/// The suffix used by Hadoop (and EMR) to mark folders.
+const HADOOP_SUFFIX: &str = "_$folder$";
+
+/// Container struct for zero-byte objects and folder markers.
+///
+/// Markers are placeholder objects created by consoles and tools to show
+/// an "empty" folder. A marker is only reported as an empty folder if no
+/// other object exists beneath it, so markers and folders containing objects
+/// are kept as hashes (to limit memory) regardless of the order of a listing.
+///
+/// Only a bounded number of marker names are kept as examples; candidates
+/// are dropped as soon as contents are found beneath them, which frees up
+/// space for later markers, but some empty folders may still go unnamed.
+pub struct Markers {
+    zero_byte: u64,
+    slash_markers: u64,
+    hadoop_markers: u64,
+    marker_bytes: u64,
+    markers: HashSet<u64>,
+    contents: HashSet<u64>,
+    candidates: HashMap<u64, String>,
+}
+
+/// Main implementation.
+impl Markers {
+    /// Constructs a new `Markers` struct.
+    pub fn new() -> Markers {
+        Markers {
+            zero_byte: 0,
+            slash_markers: 0,
+            hadoop_markers: 0,
+            marker_bytes: 0,
+            markers: HashSet::new(),
+            contents: HashSet::new(),
+            candidates: HashMap::new(),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Markers {
+    /// Constructs a new `Markers` struct.
+    fn default() -> Markers {
+        Markers::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Markers {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let size = super::get_size(object);
+
+        // determine the path of the object, and whether it's a marker
+        let path = match folder_of(key) {
+            Some(folder) => {
+                if key.ends_with('/') {
+                    self.slash_markers += 1;
+                } else {
+                    self.hadoop_markers += 1;
+                }
+
+                self.marker_bytes += size;
+
+                // keep the name while it could still be an empty folder
+                let hashed = hash(folder);
+
+                if self.markers.insert(hashed)
+                    && !self.contents.contains(&hashed)
+                    && self.candidates.len() < CANDIDATES
+                {
+                    self.candidates.insert(hashed, folder.to_string());
+                }
+
+                folder
+            }
+            None => {
+                if size == 0 {
+                    self.zero_byte += 1;
+                }
+                key
+            }
+        };
+
+        // every ancestor of the object has contents
+        for (idx, _) in path.match_indices('/') {
+            let hashed = hash(&path[..idx]);
+
+            if self.contents.insert(hashed) {
+                self.candidates.remove(&hashed);
+            }
+        }
+    }
+
+    /// Reports all internal statistics under the `markers` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: markers
+        report.head("markers");
+
+        report.pair("zero_byte_files", self.zero_byte);
+        report.pair("slash_markers", self.slash_markers);
+        report.pair("hadoop_markers", self.hadoop_markers);
+        report.pair("marker_bytes", self.marker_bytes);
+
+        // markers without any contents are empty folders
+        let empty = self
+            .markers
+            .iter()
+            .filter(|hashed| !self.contents.contains(hashed))
+            .count();
+
+        report.pair("empty_folders", empty);
+
+        // remaining candidates are all empty, so list them by name
+        let mut names: Vec<&String> = self.candidates.values().collect();
+        names.sort();
+
+        for (idx, folder) in names.into_iter().take(LIMIT).enumerate() {
+            report.pair(&format!("empty_folder_{}_name", idx + 1), folder);
+        }
+    }
+}
+
+/// Retrieves the folder of a marker key, if the key is a marker.
+fn folder_of(key: &str) -> Option<&str> {
+    key.strip_suffix('/')
+        .or_else(|| key.strip_suffix(HADOOP_SUFFIX))
+}
+
+/// Hashes a folder path, to avoid storing every folder.
+fn hash(folder: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    folder.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Markers;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str, size: i64) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn detecting_markers_and_empty_folders() {
+        let mut metric = Markers::new();
+
+        metric.register(&object("logs/", 0));
+        metric.register(&object("logs/file.txt", 10));
+        metric.register(&object("empty/", 0));
+        metric.register(&object("data/part-0000", 0));
+        metric.register(&object("data_$folder$", 0));
+        metric.register(&object("tmp/nested_$folder$", 0));
+        metric.register(&object("tmp_$folder$", 0));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("markers", label);
+
+        assert_eq!(get("zero_byte_files"), Some("1"));
+        assert_eq!(get("slash_markers"), Some("2"));
+        assert_eq!(get("hadoop_markers"), Some("3"));
+        assert_eq!(get("empty_folders"), Some("2"));
+        assert_eq!(get("empty_folder_1_name"), Some("empty"));
+        assert_eq!(get("empty_folder_2_name"), Some("tmp/nested"));
+    }
+
+    #[test]
+    fn bounding_empty_folder_names() {
+        let mut metric = Markers::new();
+
+        for idx in 0..super::CANDIDATES * 2 {
+            metric.register(&object(&format!("{:03}/", idx), 0));
+        }
+
+        // contents free up candidates, but never bring back dropped names
+        metric.register(&object("000/file.txt", 10));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("markers", label);
+
+        assert_eq!(metric.candidates.len(), super::CANDIDATES - 1);
+        assert_eq!(get("empty_folders"), Some("199"));
+        assert_eq!(get("empty_folder_1_name"), Some("001"));
+        assert_eq!(get("empty_folder_10_name"), Some("010"));
+        assert_eq!(get("empty_folder_11_name"), None);
+    }
+}
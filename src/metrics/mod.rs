@@ -1,15 +1,55 @@
 //! Parent metric module exposing traits around metrics gathering.
-use rusoto_s3::Object;
+use chrono::{DateTime, Utc};
+use inventory::Record;
+use lifecycle::Source;
+use options::Options;
+use report::Report;
+use rusoto_s3::{HeadObjectOutput, Object, Tag};
+use source;
 
+pub mod age;
+pub mod collisions;
+pub mod content;
+pub mod date_partitions;
+pub mod encryption;
 pub mod extensions;
 pub mod file_size;
 pub mod general;
+pub mod group_by;
+pub mod hot_spots;
+pub mod hygiene;
+pub mod inventory;
+pub mod keys;
+pub mod lifecycle;
+pub mod markers;
 pub mod modification;
+pub mod owners;
+pub mod partitions;
+pub mod small_files;
+pub mod tagging;
+pub mod timeline;
 
+use self::age::Age;
+use self::collisions::Collisions;
+use self::content::Content;
+use self::date_partitions::DatePartitions;
+use self::encryption::Encryption;
 use self::extensions::Extensions;
 use self::file_size::FileSize;
 use self::general::General;
+use self::group_by::GroupBy;
+use self::hot_spots::HotSpots;
+use self::hygiene::Hygiene;
+use self::inventory::Inventory;
+use self::keys::Keys;
+use self::lifecycle::Lifecycle;
+use self::markers::Markers;
 use self::modification::Modification;
+use self::owners::Owners;
+use self::partitions::Partitions;
+use self::small_files::SmallFiles;
+use self::tagging::Tagging;
+use self::timeline::Timeline;
 
 /// Metric trait to represent a metric tracker for S3.
 ///
@@ -20,28 +60,173 @@ pub trait Metric {
     /// Registers an S3 object for statistics.
     fn register(&mut self, object: &Object);
 
-    /// Prints the internal statistics.
-    fn print(&self);
+    /// Registers the headers of an S3 object for statistics.
+    ///
+    /// This is only called when deep inspection is enabled, so
+    /// most metrics can rely on the default implementation.
+    fn register_head(&mut self, _object: &Object, _head: &HeadObjectOutput) {}
+
+    /// Registers an S3 object whose headers could not be fetched.
+    ///
+    /// This is only called when deep inspection is enabled, so
+    /// most metrics can rely on the default implementation.
+    fn register_failed_head(&mut self, _object: &Object) {}
+
+    /// Registers the tags of an S3 object for statistics.
+    ///
+    /// This is only called when tag inspection is enabled, so
+    /// most metrics can rely on the default implementation.
+    fn register_tags(&mut self, _object: &Object, _tags: &[Tag]) {}
+
+    /// Registers the inventory record of an S3 object for statistics.
+    ///
+    /// This is only called when reading from an inventory report, so
+    /// most metrics can rely on the default implementation.
+    fn register_inventory(&mut self, _object: &Object, _record: &Record) {}
+
+    /// Reports the internal statistics.
+    fn report(&self, report: &mut Report);
 }
 
+/// Constructor of a `Metric` from options, if applicable to the scan.
+type Builder = fn(&Options) -> Option<Box<dyn Metric>>;
+
+/// Registry of all metrics, keyed by the section they report under.
+///
+/// Entries are in the order that sections appear in the output, and any
+/// metric requiring extra options is only constructed when they're set.
+const REGISTRY: &[(&str, Builder)] = &[
+    ("general", |options| {
+        Some(Box::new(General::new(&options.prefix)))
+    }),
+    ("file_size", |_| Some(Box::new(FileSize::new()))),
+    ("extensions", |_| Some(Box::new(Extensions::new()))),
+    // key shapes are only tracked when requested
+    ("keys", |options| {
+        when(options.keys, || Box::new(Keys::new()))
+    }),
+    // key names are only audited when requested
+    ("hygiene", |options| {
+        when(options.hygiene, || Box::new(Hygiene::new()))
+    }),
+    // markers are only tracked when requested
+    ("markers", |options| {
+        when(options.markers, || Box::new(Markers::new()))
+    }),
+    ("modification", |options| {
+        Some(Box::new(Modification::new(&options.formatter)))
+    }),
+    ("age", |options| {
+        Some(Box::new(Age::new(options.reference, &options.formatter)))
+    }),
+    // timelines are only tracked when a period is requested
+    ("timeline", |options| {
+        options
+            .timeline
+            .map(|period| Box::new(Timeline::new(period, &options.formatter)) as Box<dyn Metric>)
+    }),
+    // owners are only tracked when requested
+    ("owners", |options| {
+        when(options.owners, || Box::new(Owners::new()))
+    }),
+    // partitions are only detected when requested
+    ("partitions", |options| {
+        when(options.partitions, || Box::new(Partitions::new()))
+    }),
+    // date layouts are only detected when requested
+    ("date_partitions", |options| {
+        when(options.date_partitions, || Box::new(DatePartitions::new()))
+    }),
+    // small files are only analysed when a threshold is provided
+    ("small_files", |options| {
+        options
+            .small_files
+            .map(|threshold| Box::new(SmallFiles::new(threshold)) as Box<dyn Metric>)
+    }),
+    // hot spots are only analysed when requested
+    ("hot_spots", |options| {
+        when(options.hot_spots, || {
+            Box::new(HotSpots::new(&options.prefix))
+        })
+    }),
+    // collisions are only detected when requested
+    ("collisions", |options| {
+        when(options.collisions, || Box::new(Collisions::new()))
+    }),
+    // headers are only inspected in deep mode
+    ("content", |options| {
+        when(options.deep, || Box::new(Content::new()))
+    }),
+    ("encryption", |options| {
+        when(options.deep, || Box::new(Encryption::new()))
+    }),
+    // tags are only inspected when requested
+    ("tagging", |options| {
+        when(options.tags, || Box::new(Tagging::new()))
+    }),
+    // inventory columns are only available from inventory reports
+    ("inventory", |options| match options.source {
+        source::Source::Inventory(_) => Some(Box::new(Inventory::new(options.reference))),
+        _ => None,
+    }),
+    // lifecycles are only simulated when rules are provided
+    ("lifecycle", |options| match options.lifecycle {
+        Some(Source::Rules(ref rules)) => {
+            Some(Box::new(Lifecycle::new(rules.clone(), options.reference)))
+        }
+        _ => None,
+    }),
+];
+
 /// Returns a chain of `Metric` objects in deterministic order.
-pub fn chain(prefix: &Option<String>) -> Vec<Box<dyn Metric>> {
-    vec![
-        Box::new(General::new(prefix)),
-        Box::new(FileSize::new()),
-        Box::new(Extensions::new()),
-        Box::new(Modification::new()),
-    ]
+///
+/// Only the sections selected by the options are included in the chain,
+/// with any custom groups following all of the built-in sections.
+pub fn chain(options: &Options) -> Vec<Box<dyn Metric>> {
+    let mut chain: Vec<Box<dyn Metric>> = REGISTRY
+        .iter()
+        .filter(|(name, _)| options.selects(name))
+        .filter_map(|(_, build)| build(options))
+        .collect();
+
+    // custom groups are defined by configuration
+    for group in &options.groups {
+        if options.selects(&group.name) {
+            chain.push(Box::new(GroupBy::new(group.clone(), &options.formatter)));
+        }
+    }
+
+    chain
+}
+
+/// Returns the names of all sections available in the registry.
+pub fn sections() -> Vec<&'static str> {
+    REGISTRY.iter().map(|(name, _)| *name).collect()
 }
 
-/// Retrieves the key of an `Object` as a `&String`.
+/// Constructs a `Metric` only when a condition is met.
+fn when<F>(condition: bool, build: F) -> Option<Box<dyn Metric>>
+where
+    F: FnOnce() -> Box<dyn Metric>,
+{
+    if condition {
+        Some(build())
+    } else {
+        None
+    }
+}
+
+/// Retrieves the key of an `Object` as a `&str`.
 pub(in metrics) fn get_key(object: &Object) -> &str {
-    &*unwrap_opt(&object.key, "objects should have a key")
+    unwrap_opt(&object.key, "objects should have a key").as_str()
 }
 
-/// Retrieves the modification time of an `Object` as a `&String`.
-pub(in metrics) fn get_modified(object: &Object) -> &String {
-    unwrap_opt(&object.last_modified, "objects should have a modified date")
+/// Retrieves the modification time of an `Object` as a `DateTime`.
+///
+/// If the modification time cannot be parsed, `None` is returned.
+pub(in metrics) fn get_modified(object: &Object) -> Option<DateTime<Utc>> {
+    let modified = unwrap_opt(&object.last_modified, "objects should have a modified date");
+    ::dates::parse(modified)
 }
 
 /// Retrieves the size of an `Object` as a `u64`.
@@ -1,22 +1,29 @@
 //! Modification metrics tracking for S3 objects.
 use bounded::Bounded;
+use chrono::{DateTime, Utc};
+use dates::Formatter;
 use rusoto_s3::Object;
 
 use super::Metric;
+use report::Report;
 
 /// Container struct for modificaton metrics tracked by S3.
 pub struct Modification {
-    earliest_file: Bounded<String>,
-    latest_file: Bounded<String>,
+    earliest_file: Bounded<DateTime<Utc>>,
+    latest_file: Bounded<DateTime<Utc>>,
+    invalid_dates: u64,
+    formatter: Formatter,
 }
 
 /// Main implementation.
 impl Modification {
     /// Constructs a new `Modification` struct.
-    pub(super) fn new() -> Modification {
+    pub fn new(formatter: &Formatter) -> Modification {
         Modification {
-            latest_file: Bounded::new("".into()),
-            earliest_file: Bounded::new("".into()),
+            latest_file: Bounded::new(DateTime::default()),
+            earliest_file: Bounded::new(DateTime::default()),
+            invalid_dates: 0,
+            formatter: formatter.clone(),
         }
     }
 }
@@ -25,27 +32,39 @@ impl Modification {
 impl Metric for Modification {
     /// Registers an S3 `Object` with this metric struct.
     fn register(&mut self, object: &Object) {
+        // parse the date, tracking failures
+        let modified = match super::get_modified(object) {
+            Some(modified) => modified,
+            None => {
+                self.invalid_dates += 1;
+                return;
+            }
+        };
+
         ::bounded::apply(
             &mut self.earliest_file,
             &mut self.latest_file,
             super::get_key(object),
-            super::get_modified(object),
+            &modified,
         );
     }
 
-    /// Prints out all internal statistics under the `modification` header.
-    fn print(&self) {
+    /// Reports all internal statistics under the `modification` header.
+    fn report(&self, report: &mut Report) {
         // next segment: modification
-        ::util::log_head("modification");
+        report.head("modification");
 
         // log out the bounds of the earliest file
-        ::util::log_bound("earliest_file", &self.earliest_file, |date| {
-            ::util::log_pair("earliest_file_date", date);
+        report.bound("earliest_file", &self.earliest_file, |report, date| {
+            report.pair("earliest_file_date", self.formatter.format(&date));
         });
 
         // log out the bounds of the latest file
-        ::util::log_bound("latest_file", &self.latest_file, |date| {
-            ::util::log_pair("latest_file_date", date);
+        report.bound("latest_file", &self.latest_file, |report, date| {
+            report.pair("latest_file_date", self.formatter.format(&date));
         });
+
+        // log out any dates which could not be parsed
+        report.pair("invalid_file_dates", self.invalid_dates);
     }
 }
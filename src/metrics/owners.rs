@@ -0,0 +1,149 @@
+//! Owner metrics tracking for S3 objects.
+use rusoto_s3::Object;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Metric;
+use report::Report;
+
+/// Container struct for owner metrics tracked by S3.
+pub struct Owners {
+    owners: HashMap<String, OwnerStats>,
+    unknown: (u64, u64),
+}
+
+/// Statistics tracked for a single owner.
+#[derive(Default)]
+struct OwnerStats {
+    name: Option<String>,
+    files: u64,
+    bytes: u64,
+}
+
+/// Main implementation.
+impl Owners {
+    /// Constructs a new `Owners` struct.
+    pub fn new() -> Owners {
+        Owners {
+            owners: HashMap::new(),
+            unknown: (0, 0),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Owners {
+    /// Constructs a new `Owners` struct.
+    fn default() -> Owners {
+        Owners::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Owners {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        // pull various metadata
+        let size = super::get_size(object);
+        let owner = object.owner.as_ref();
+
+        // owners are only included when requested, and require an ID
+        let id = match owner.and_then(|owner| owner.id.as_ref()) {
+            Some(id) => id,
+            None => {
+                self.unknown.0 += 1;
+                self.unknown.1 += size;
+                return;
+            }
+        };
+
+        // fetch the stats for this owner
+        let stats = self.owners.entry(id.to_string()).or_default();
+
+        // display names are not always provided
+        if stats.name.is_none() {
+            stats.name = owner.and_then(|owner| owner.display_name.clone());
+        }
+
+        stats.files += 1;
+        stats.bytes += size;
+    }
+
+    /// Reports all internal statistics under the `owners` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: owners
+        report.head("owners");
+        report.pair("unique_owners", self.owners.len());
+
+        // sort owners by the number of bytes they own, then by ID
+        let mut owners: Vec<_> = self.owners.iter().collect();
+        owners.sort_by_key(|(id, stats)| (Reverse(stats.bytes), *id));
+
+        // log out each owner, ranked by size
+        for (idx, (id, stats)) in owners.into_iter().enumerate() {
+            let label = format!("owner_{}", idx + 1);
+
+            report.pair(&format!("{}_id", label), id);
+
+            if let Some(ref name) = stats.name {
+                report.pair(&format!("{}_name", label), name);
+            }
+
+            report.pair(&format!("{}_files", label), stats.files);
+            report.pair(
+                &format!("{}_size", label),
+                ::util::convert_bytes(stats.bytes),
+            );
+            report.pair(&format!("{}_bytes", label), stats.bytes);
+        }
+
+        // log out anything without an owner attached
+        report.pair("unknown_owner_files", self.unknown.0);
+        report.pair("unknown_owner_bytes", self.unknown.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Owners;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::{Object, Owner};
+
+    fn object(size: i64, id: Option<&str>, name: Option<&str>) -> Object {
+        Object {
+            key: Some("file.txt".into()),
+            size: Some(size),
+            owner: id.map(|id| Owner {
+                id: Some(id.into()),
+                display_name: name.map(|name| name.into()),
+            }),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn reporting_owners() {
+        let mut metric = Owners::new();
+
+        metric.register(&object(10, Some("small"), None));
+        metric.register(&object(20, Some("large"), None));
+        metric.register(&object(30, Some("large"), Some("admin")));
+        metric.register(&object(5, None, None));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("owners", label);
+
+        assert_eq!(get("unique_owners"), Some("2"));
+        assert_eq!(get("owner_1_id"), Some("large"));
+        assert_eq!(get("owner_1_name"), Some("admin"));
+        assert_eq!(get("owner_1_files"), Some("2"));
+        assert_eq!(get("owner_1_bytes"), Some("50"));
+        assert_eq!(get("owner_2_id"), Some("small"));
+        assert_eq!(get("owner_2_name"), None);
+        assert_eq!(get("unknown_owner_files"), Some("1"));
+        assert_eq!(get("unknown_owner_bytes"), Some("5"));
+    }
+}
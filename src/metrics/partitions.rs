@@ -0,0 +1,316 @@
+//! Partition metrics tracking for S3 objects.
+use rusoto_s3::Object;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use super::Metric;
+use report::Report;
+
+/// The number of partitions to include in each ranked list.
+const LIMIT: usize = 10;
+
+/// The size below which a file is considered tiny (1MB).
+const TINY_FILE_BYTES: u64 = 1024 * 1024;
+
+/// The number of tiny files required to flag a partition.
+const TINY_FILE_COUNT: u64 = 10;
+
+/// The ratio against the mean of a table required to flag a partition.
+const SKEW_RATIO: f64 = 4.0;
+
+/// Container struct for Hive-style partition metrics.
+///
+/// Partitions are detected from `key=value` segments in the folders of a
+/// key, such as `table/dt=2018-01-01/region=eu/part-0000.parquet`. Anything
+/// before the first partition segment is treated as the table.
+pub struct Partitions {
+    partitions: HashMap<String, Totals>,
+    columns: HashMap<(String, String), HashSet<String>>,
+    unpartitioned: (u64, u64),
+}
+
+/// Totals tracked for a single partition.
+#[derive(Default)]
+struct Totals {
+    files: u64,
+    bytes: u64,
+    tiny: u64,
+}
+
+/// Main implementation.
+impl Partitions {
+    /// Constructs a new `Partitions` struct.
+    pub fn new() -> Partitions {
+        Partitions {
+            partitions: HashMap::new(),
+            columns: HashMap::new(),
+            unpartitioned: (0, 0),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Partitions {
+    /// Constructs a new `Partitions` struct.
+    fn default() -> Partitions {
+        Partitions::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Partitions {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let size = super::get_size(object);
+
+        // locate the partition of the key, if any
+        let (table, partition) = match split(key) {
+            Some(split) => split,
+            None => {
+                self.unpartitioned.0 += 1;
+                self.unpartitioned.1 += size;
+                return;
+            }
+        };
+
+        // only new partitions can introduce new column values, and
+        // columns are tracked per table as names are often reused
+        if !self.partitions.contains_key(partition) {
+            for (column, value) in segments(partition) {
+                self.columns
+                    .entry((table.to_string(), column.to_string()))
+                    .or_default()
+                    .insert(value.to_string());
+            }
+        }
+
+        let totals = self.partitions.entry(partition.to_string()).or_default();
+
+        totals.files += 1;
+        totals.bytes += size;
+
+        if size < TINY_FILE_BYTES {
+            totals.tiny += 1;
+        }
+    }
+
+    /// Reports all internal statistics under the `partitions` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: partitions
+        report.head("partitions");
+
+        // group partitions into their tables
+        let mut tables: HashMap<&str, Vec<(&String, &Totals)>> = HashMap::new();
+
+        for (partition, totals) in &self.partitions {
+            let (table, _) = split(partition).expect("partitions should be valid");
+            tables.entry(table).or_default().push((partition, totals));
+        }
+
+        report.pair("total_tables", tables.len());
+        report.pair("total_partitions", self.partitions.len());
+        report.pair(
+            "partitioned_files",
+            self.partitions.values().map(|t| t.files).sum::<u64>(),
+        );
+        report.pair(
+            "partitioned_bytes",
+            self.partitions.values().map(|t| t.bytes).sum::<u64>(),
+        );
+        report.pair("unpartitioned_files", self.unpartitioned.0);
+        report.pair("unpartitioned_bytes", self.unpartitioned.1);
+
+        // log out the columns with the most distinct values
+        let mut columns: Vec<_> = self.columns.iter().collect();
+        columns.sort_by_key(|(name, values)| (Reverse(values.len()), *name));
+
+        report.pair("total_columns", columns.len());
+
+        for (idx, ((table, name), values)) in columns.iter().take(LIMIT).enumerate() {
+            let label = format!("column_{}", idx + 1);
+
+            report.pair(&format!("{}_table", label), table);
+            report.pair(&format!("{}_name", label), name);
+            report.pair(&format!("{}_values", label), values.len());
+        }
+
+        // log out the largest partitions
+        let mut largest: Vec<_> = self.partitions.iter().collect();
+        largest.sort_by_key(|(name, totals)| (Reverse(totals.bytes), *name));
+
+        for (idx, (name, totals)) in largest.iter().take(LIMIT).enumerate() {
+            let label = format!("partition_{}", idx + 1);
+
+            report.pair(&format!("{}_name", label), name);
+            report.pair(&format!("{}_files", label), totals.files);
+            report.pair(
+                &format!("{}_size", label),
+                ::util::convert_bytes(totals.bytes),
+            );
+            report.pair(&format!("{}_bytes", label), totals.bytes);
+        }
+
+        // find partitions far larger than the rest of their table
+        let mut skewed: Vec<(&String, &Totals, f64)> = Vec::new();
+
+        for partitions in tables.values().filter(|p| p.len() > 1) {
+            let total: u64 = partitions.iter().map(|(_, t)| t.bytes).sum();
+            let mean = total as f64 / partitions.len() as f64;
+
+            for (name, totals) in partitions {
+                let ratio = totals.bytes as f64 / mean;
+
+                if ratio >= SKEW_RATIO {
+                    skewed.push((name, totals, ratio));
+                }
+            }
+        }
+
+        skewed.sort_by(|(l, _, left), (r, _, right)| right.total_cmp(left).then(l.cmp(r)));
+
+        report.pair("skewed_partitions", skewed.len());
+
+        for (idx, (name, totals, ratio)) in skewed.iter().take(LIMIT).enumerate() {
+            let label = format!("skewed_{}", idx + 1);
+
+            report.pair(&format!("{}_name", label), name);
+            report.pair(&format!("{}_bytes", label), totals.bytes);
+            report.pair(&format!("{}_ratio", label), format!("{:.2}", ratio));
+        }
+
+        // find partitions containing many tiny files
+        let mut tiny: Vec<_> = self
+            .partitions
+            .iter()
+            .filter(|(_, totals)| totals.tiny >= TINY_FILE_COUNT)
+            .collect();
+
+        tiny.sort_by_key(|(name, totals)| (Reverse(totals.tiny), *name));
+
+        report.pair("tiny_file_partitions", tiny.len());
+
+        for (idx, (name, totals)) in tiny.iter().take(LIMIT).enumerate() {
+            let label = format!("tiny_{}", idx + 1);
+
+            report.pair(&format!("{}_name", label), name);
+            report.pair(&format!("{}_files", label), totals.files);
+            report.pair(&format!("{}_tiny_files", label), totals.tiny);
+        }
+    }
+}
+
+/// Splits a key into its table and partition, if it's partitioned.
+///
+/// The table is everything before the first partition segment, and the
+/// partition is everything up to (and including) the last.
+fn split(key: &str) -> Option<(&str, &str)> {
+    let mut offset = 0;
+    let mut first = None;
+    let mut last = None;
+
+    // only folders can be partitions, so skip the file name
+    while let Some(idx) = key[offset..].find('/') {
+        let end = offset + idx + 1;
+
+        if is_partition(&key[offset..end - 1]) {
+            first.get_or_insert(offset);
+            last = Some(end);
+        }
+
+        offset = end;
+    }
+
+    Some((&key[..first?], &key[..last?]))
+}
+
+/// Retrieves all column/value pairs from the segments of a partition.
+fn segments(partition: &str) -> impl Iterator<Item = (&str, &str)> {
+    partition
+        .split('/')
+        .filter(|segment| is_partition(segment))
+        .filter_map(|segment| segment.split_once('='))
+}
+
+/// Determines whether a path segment is a `key=value` partition.
+fn is_partition(segment: &str) -> bool {
+    match segment.split_once('=') {
+        Some((column, value)) => {
+            !column.is_empty()
+                && !value.is_empty()
+                && column
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Partitions;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str, size: i64) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn splitting_partitions() {
+        assert_eq!(
+            super::split("table/dt=2018-01-01/region=eu/part-0000.parquet"),
+            Some(("table/", "table/dt=2018-01-01/region=eu/"))
+        );
+        assert_eq!(
+            super::split("dt=2018-01-01/file.txt"),
+            Some(("", "dt=2018-01-01/"))
+        );
+        assert_eq!(super::split("table/a=b.txt"), None);
+        assert_eq!(super::split("table/=value/file.txt"), None);
+        assert_eq!(super::split("table/file.txt"), None);
+    }
+
+    #[test]
+    fn reporting_partitions() {
+        let mut metric = Partitions::new();
+
+        for idx in 0..10 {
+            metric.register(&object(&format!("t/dt=1/region=eu/{}.parquet", idx), 1));
+        }
+
+        metric.register(&object("t/dt=2/region=eu/0.parquet", 1));
+        metric.register(&object("t/dt=2/region=us/0.parquet", 1));
+        metric.register(&object("t/dt=3/region=us/0.parquet", 1));
+        metric.register(&object("t/dt=4/region=us/0.parquet", 1));
+        metric.register(&object("t/dt=5/region=us/0.parquet", 1));
+        metric.register(&object("other/file.txt", 5));
+        metric.register(&object("u/dt=1/0.parquet", 1));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        assert_eq!(report.get("partitions", "total_tables"), Some("2"));
+        assert_eq!(report.get("partitions", "total_partitions"), Some("7"));
+        assert_eq!(report.get("partitions", "unpartitioned_files"), Some("1"));
+        assert_eq!(report.get("partitions", "total_columns"), Some("3"));
+        assert_eq!(report.get("partitions", "column_1_table"), Some("t/"));
+        assert_eq!(report.get("partitions", "column_1_name"), Some("dt"));
+        assert_eq!(report.get("partitions", "column_1_values"), Some("5"));
+        assert_eq!(report.get("partitions", "column_2_values"), Some("2"));
+        assert_eq!(report.get("partitions", "column_3_table"), Some("u/"));
+        assert_eq!(report.get("partitions", "column_3_values"), Some("1"));
+        assert_eq!(report.get("partitions", "skewed_partitions"), Some("1"));
+        assert_eq!(
+            report.get("partitions", "skewed_1_name"),
+            Some("t/dt=1/region=eu/")
+        );
+        assert_eq!(report.get("partitions", "tiny_file_partitions"), Some("1"));
+    }
+}
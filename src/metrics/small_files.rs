@@ -0,0 +1,279 @@
+//! Small file metrics tracking for S3 objects.
+use lifecycle;
+use rusoto_s3::Object;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Metric;
+use report::Report;
+
+/// The number of prefixes to include in the ranked list.
+const LIMIT: usize = 10;
+
+/// The number of bytes in a gigabyte, for pricing.
+const GIGABYTE: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// The minimum billable size of objects in infrequent access classes.
+const MINIMUM_BYTES: u64 = 128 * 1024;
+
+/// Storage classes which bill a minimum object size.
+const MINIMUM_CLASSES: [&str; 3] = ["STANDARD_IA", "ONEZONE_IA", "GLACIER_IR"];
+
+/// The price of 1,000 `GET` requests in USD, based on `us-east-1` list prices.
+const GET_PRICE: f64 = 0.0004;
+
+/// The price of 1,000 `LIST` requests in USD, based on `us-east-1` list prices.
+const LIST_PRICE: f64 = 0.005;
+
+/// Container struct for small file metrics.
+///
+/// Objects below a threshold are tracked per prefix (the folder containing
+/// the object), to find the prefixes most in need of compaction.
+pub struct SmallFiles {
+    threshold: u64,
+    prefixes: HashMap<String, Totals>,
+    minimum: (u64, u64),
+    minimum_cost: f64,
+    untiered: (u64, u64),
+    untiered_cost: f64,
+}
+
+/// Totals tracked for a single prefix.
+#[derive(Default)]
+struct Totals {
+    files: u64,
+    bytes: u64,
+    small_files: u64,
+    small_bytes: u64,
+}
+
+/// Main implementation.
+impl SmallFiles {
+    /// Constructs a new `SmallFiles` struct.
+    pub fn new(threshold: u64) -> SmallFiles {
+        SmallFiles {
+            threshold,
+            prefixes: HashMap::new(),
+            minimum: (0, 0),
+            minimum_cost: 0.0,
+            untiered: (0, 0),
+            untiered_cost: 0.0,
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for SmallFiles {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        let key = super::get_key(object);
+        let size = super::get_size(object);
+
+        let prefix = match key.rfind('/') {
+            Some(idx) => &key[..=idx],
+            None => ::util::ROOT,
+        };
+
+        let totals = self.prefixes.entry(prefix.to_string()).or_default();
+
+        totals.files += 1;
+        totals.bytes += size;
+
+        if size < self.threshold {
+            totals.small_files += 1;
+            totals.small_bytes += size;
+        }
+
+        // track objects affected by minimum sizes, regardless of threshold
+        if size >= MINIMUM_BYTES {
+            return;
+        }
+
+        match object.storage_class.as_deref() {
+            // billed as if they were the minimum size
+            Some(class) if MINIMUM_CLASSES.contains(&class) => {
+                let price = lifecycle::price(class).unwrap_or(0.0);
+                let penalty = MINIMUM_BYTES - size;
+
+                self.minimum.0 += 1;
+                self.minimum.1 += penalty;
+                self.minimum_cost += price * penalty as f64 / GIGABYTE;
+            }
+            // never moved out of the frequent access tier, missing out on
+            // the difference to the infrequent access tier
+            Some("INTELLIGENT_TIERING") => {
+                let frequent = lifecycle::price("INTELLIGENT_TIERING").unwrap_or(0.0);
+                let infrequent = lifecycle::price("STANDARD_IA").unwrap_or(0.0);
+
+                self.untiered.0 += 1;
+                self.untiered.1 += size;
+                self.untiered_cost += (frequent - infrequent) * size as f64 / GIGABYTE;
+            }
+            _ => (),
+        }
+    }
+
+    /// Reports all internal statistics under the `small_files` header.
+    fn report(&self, report: &mut Report) {
+        let total = |get: fn(&Totals) -> u64| self.prefixes.values().map(get).sum::<u64>();
+        let percent = |part: u64, whole: u64| match whole {
+            0 => 0.0,
+            v => part as f64 / v as f64 * 100.0,
+        };
+
+        let files = total(|t| t.files);
+        let bytes = total(|t| t.bytes);
+        let small_files = total(|t| t.small_files);
+        let small_bytes = total(|t| t.small_bytes);
+
+        // next segment: small_files
+        report.head("small_files");
+
+        report.pair("threshold_bytes", self.threshold);
+        report.pair("small_files", small_files);
+        report.pair("small_bytes", small_bytes);
+        report.pair(
+            "small_files_percent",
+            format!("{:.2}", percent(small_files, files)),
+        );
+        report.pair(
+            "small_bytes_percent",
+            format!("{:.2}", percent(small_bytes, bytes)),
+        );
+
+        // log out the cost of reading and listing all small files once
+        report.pair(
+            "small_get_cost_usd",
+            format!("{:.2}", small_files as f64 / 1000.0 * GET_PRICE),
+        );
+        report.pair(
+            "small_list_cost_usd",
+            format!("{:.2}", small_files as f64 / 1000.0 / 1000.0 * LIST_PRICE),
+        );
+
+        // log out the monthly penalty of minimum billable sizes
+        report.pair("minimum_size_files", self.minimum.0);
+        report.pair("minimum_size_penalty_bytes", self.minimum.1);
+        report.pair(
+            "minimum_size_monthly_penalty_usd",
+            format!("{:.2}", self.minimum_cost),
+        );
+        report.pair("untiered_files", self.untiered.0);
+        report.pair("untiered_bytes", self.untiered.1);
+        report.pair(
+            "untiered_monthly_penalty_usd",
+            format!("{:.2}", self.untiered_cost),
+        );
+
+        // rank prefixes by their number of small files
+        let mut prefixes: Vec<_> = self
+            .prefixes
+            .iter()
+            .filter(|(_, totals)| totals.small_files > 1)
+            .collect();
+
+        prefixes.sort_by_key(|(name, totals)| (Reverse(totals.small_files), *name));
+
+        report.pair("compaction_prefixes", prefixes.len());
+
+        for (idx, (name, totals)) in prefixes.iter().take(LIMIT).enumerate() {
+            let label = format!("prefix_{}", idx + 1);
+
+            report.pair(&format!("{}_name", label), name);
+            report.pair(&format!("{}_files", label), totals.files);
+            report.pair(&format!("{}_small_files", label), totals.small_files);
+            report.pair(
+                &format!("{}_small_percent", label),
+                format!("{:.2}", percent(totals.small_files, totals.files)),
+            );
+            report.pair(
+                &format!("{}_average_bytes", label),
+                totals.bytes / totals.files,
+            );
+        }
+    }
+}
+
+/// Parses a size threshold such as `128KB`, `1MB` or `512`.
+///
+/// Units are treated as powers of 1024, to match the minimum sizes
+/// documented by AWS (which are written as `128KB`).
+pub fn parse_size(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+
+    let (value, unit) = input.split_at(split);
+    let value: u64 = value.parse().ok()?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return None,
+    };
+
+    value.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallFiles;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(key: &str, size: i64, storage_class: &str) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            last_modified: Some("2018-01-01T00:00:00.000Z".into()),
+            storage_class: Some(storage_class.into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn parsing_sizes() {
+        assert_eq!(super::parse_size("512"), Some(512));
+        assert_eq!(super::parse_size("128KB"), Some(128 * 1024));
+        assert_eq!(super::parse_size("1 mb"), Some(1024 * 1024));
+        assert_eq!(super::parse_size("1TB"), None);
+        assert_eq!(super::parse_size("KB"), None);
+    }
+
+    #[test]
+    fn reporting_small_files() {
+        let mut metric = SmallFiles::new(1024);
+
+        for idx in 0..3 {
+            metric.register(&object(&format!("logs/{}.txt", idx), 10, "STANDARD"));
+        }
+
+        metric.register(&object("logs/large.txt", 4096, "STANDARD"));
+        metric.register(&object("data/1.txt", 10, "STANDARD_IA"));
+        metric.register(&object("data/2.txt", 10, "INTELLIGENT_TIERING"));
+        metric.register(&object("file.txt", 2048, "STANDARD"));
+        metric.register(&object("a.txt", 10, "STANDARD"));
+        metric.register(&object("b.txt", 10, "STANDARD"));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("small_files", label);
+
+        assert_eq!(get("small_files"), Some("7"));
+        assert_eq!(get("small_files_percent"), Some("77.78"));
+        assert_eq!(get("minimum_size_files"), Some("1"));
+        assert_eq!(get("minimum_size_penalty_bytes"), Some("131062"));
+        assert_eq!(get("untiered_files"), Some("1"));
+        assert_eq!(get("untiered_monthly_penalty_usd"), Some("0.00"));
+        assert_eq!(get("compaction_prefixes"), Some("3"));
+        assert_eq!(get("prefix_1_name"), Some("logs/"));
+        assert_eq!(get("prefix_1_small_files"), Some("3"));
+        assert_eq!(get("prefix_2_name"), Some("<root>"));
+        assert_eq!(get("prefix_3_name"), Some("data/"));
+    }
+}
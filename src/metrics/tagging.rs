@@ -0,0 +1,162 @@
+//! Tagging metrics tracking for S3 objects.
+use rusoto_s3::{Object, Tag};
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::Metric;
+use report::Report;
+
+/// Container struct for tagging metrics tracked by S3.
+pub struct Tagging {
+    total_keys: u64,
+    inspected_keys: u64,
+    untagged: (u64, u64),
+    tag_keys: HashMap<String, TagStats>,
+}
+
+/// Statistics tracked for a single tag key.
+///
+/// Values are only kept as hashes, as some tags (such as request IDs) can
+/// have a distinct value on every object.
+#[derive(Default)]
+struct TagStats {
+    files: u64,
+    values: HashSet<u64>,
+}
+
+/// Main implementation.
+impl Tagging {
+    /// Constructs a new `Tagging` struct.
+    pub fn new() -> Tagging {
+        Tagging {
+            total_keys: 0,
+            inspected_keys: 0,
+            untagged: (0, 0),
+            tag_keys: HashMap::new(),
+        }
+    }
+}
+
+/// Default implementation.
+impl Default for Tagging {
+    /// Constructs a new `Tagging` struct.
+    fn default() -> Tagging {
+        Tagging::new()
+    }
+}
+
+/// Metric implementation.
+impl Metric for Tagging {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, _object: &Object) {
+        self.total_keys += 1;
+    }
+
+    /// Registers the tags of an S3 `Object` with this metric struct.
+    fn register_tags(&mut self, object: &Object, tags: &[Tag]) {
+        // count another inspected key
+        self.inspected_keys += 1;
+
+        // track untagged objects separately
+        if tags.is_empty() {
+            self.untagged.0 += 1;
+            self.untagged.1 += super::get_size(object);
+            return;
+        }
+
+        // track each key, alongside the values it has
+        for tag in tags {
+            let stats = self.tag_keys.entry(tag.key.clone()).or_default();
+
+            stats.files += 1;
+            stats.values.insert(hash(&tag.value));
+        }
+    }
+
+    /// Reports all internal statistics under the `tagging` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: tagging
+        report.head("tagging");
+
+        // log out how many objects could be inspected
+        report.pair("inspected_files", self.inspected_keys);
+        report.pair("uninspected_files", self.total_keys - self.inspected_keys);
+
+        // log out the objects without any tags
+        report.pair("untagged_files", self.untagged.0);
+        report.pair("untagged_size", ::util::convert_bytes(self.untagged.1));
+        report.pair("untagged_bytes", self.untagged.1);
+        report.pair("unique_tag_keys", self.tag_keys.len());
+
+        // sort tag keys by their frequency, then by name
+        let mut tag_keys: Vec<_> = self.tag_keys.iter().collect();
+        tag_keys.sort_by_key(|(key, stats)| (Reverse(stats.files), *key));
+
+        // log out each tag key, ranked by frequency
+        for (idx, (key, stats)) in tag_keys.into_iter().enumerate() {
+            let label = format!("tag_key_{}", idx + 1);
+
+            report.pair(&label, key);
+            report.pair(&format!("{}_files", label), stats.files);
+            report.pair(&format!("{}_values", label), stats.values.len());
+        }
+    }
+}
+
+/// Hashes a tag value, to avoid storing every value.
+fn hash(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tagging;
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::{Object, Tag};
+
+    fn tag(key: &str, value: &str) -> Tag {
+        Tag {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn reporting_tags() {
+        let mut metric = Tagging::new();
+        let object = Object {
+            key: Some("file.txt".into()),
+            size: Some(10),
+            ..Object::default()
+        };
+
+        for _ in 0..4 {
+            metric.register(&object);
+        }
+
+        metric.register_tags(&object, &[tag("team", "data"), tag("tier", "hot")]);
+        metric.register_tags(&object, &[tag("team", "web"), tag("env", "prod")]);
+        metric.register_tags(&object, &[]);
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("tagging", label);
+
+        assert_eq!(get("inspected_files"), Some("3"));
+        assert_eq!(get("uninspected_files"), Some("1"));
+        assert_eq!(get("untagged_files"), Some("1"));
+        assert_eq!(get("untagged_bytes"), Some("10"));
+        assert_eq!(get("unique_tag_keys"), Some("3"));
+        assert_eq!(get("tag_key_1"), Some("team"));
+        assert_eq!(get("tag_key_1_files"), Some("2"));
+        assert_eq!(get("tag_key_1_values"), Some("2"));
+        assert_eq!(get("tag_key_2"), Some("env"));
+        assert_eq!(get("tag_key_3"), Some("tier"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Timeline metrics tracking for S3 objects.
+use chrono::{Datelike, Duration, NaiveDate};
+use dates::Formatter;
+use rusoto_s3::Object;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::Metric;
+use report::Report;
+
+/// Periods available to bucket a timeline into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+/// Main implementation.
+impl Period {
+    /// Locates the first date of the period containing a date.
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Month => date.with_day(1).expect("first day should always exist"),
+        }
+    }
+
+    /// Locates the first date of the period following a period start.
+    fn next(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date + Duration::days(1),
+            Period::Week => date + Duration::weeks(1),
+            Period::Month => {
+                let (year, month) = match date.month() {
+                    12 => (date.year() + 1, 1),
+                    m => (date.year(), m + 1),
+                };
+                NaiveDate::from_ymd_opt(year, month, 1).expect("first day should always exist")
+            }
+        }
+    }
+
+    /// Formats the label of the period starting at a date.
+    fn label(self, date: NaiveDate) -> String {
+        let format = match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%G-W%V",
+            Period::Month => "%Y-%m",
+        };
+        date.format(format).to_string()
+    }
+
+    /// Returns the name of this period.
+    fn name(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+}
+
+/// String parsing implementation.
+impl FromStr for Period {
+    type Err = String;
+
+    /// Parses a `Period` from its name.
+    fn from_str(s: &str) -> Result<Period, String> {
+        match s {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(format!("Invalid timeline period: {}", s)),
+        }
+    }
+}
+
+/// Container struct for timeline metrics tracked by S3.
+///
+/// Periods are calculated in the timezone of the output, so that days line
+/// up with the timestamps written elsewhere in the report.
+pub struct Timeline {
+    period: Period,
+    formatter: Formatter,
+    periods: BTreeMap<NaiveDate, (u64, u64)>,
+}
+
+/// Main implementation.
+impl Timeline {
+    /// Constructs a new `Timeline` struct.
+    pub fn new(period: Period, formatter: &Formatter) -> Timeline {
+        Timeline {
+            period,
+            formatter: formatter.clone(),
+            periods: BTreeMap::new(),
+        }
+    }
+}
+
+/// Metric implementation.
+impl Metric for Timeline {
+    /// Registers an S3 `Object` with this metric struct.
+    fn register(&mut self, object: &Object) {
+        // unparseable dates are reported by `Modification`
+        let modified = match super::get_modified(object) {
+            Some(modified) => modified,
+            None => return,
+        };
+
+        // locate the period and increment
+        let start = self.period.start(self.formatter.date(&modified));
+        let entry = self.periods.entry(start).or_insert((0, 0));
+
+        entry.0 += 1;
+        entry.1 += super::get_size(object);
+    }
+
+    /// Reports all internal statistics under the `timeline` header.
+    fn report(&self, report: &mut Report) {
+        // next segment: timeline
+        report.head("timeline");
+        report.pair("timeline_period", self.period.name());
+
+        // count the gaps between periods, rather than listing them
+        let mut empty = 0;
+
+        for (current, next) in self.periods.keys().zip(self.periods.keys().skip(1)) {
+            let mut current = self.period.next(*current);
+
+            while current < *next {
+                empty += 1;
+                current = self.period.next(current);
+            }
+        }
+
+        report.pair("timeline_empty_periods", empty);
+
+        // only periods containing objects are listed
+        for (start, (files, bytes)) in &self.periods {
+            let label = self.period.label(*start);
+
+            report.pair(&format!("{}_files", label), files);
+            report.pair(&format!("{}_size", label), ::util::convert_bytes(*bytes));
+            report.pair(&format!("{}_bytes", label), bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Period, Timeline};
+    use chrono::NaiveDate;
+    use dates::{self, Formatter};
+    use metrics::Metric;
+    use report::Report;
+    use rusoto_s3::Object;
+
+    fn object(modified: &str) -> Object {
+        Object {
+            key: Some("file.txt".into()),
+            size: Some(1),
+            last_modified: Some(modified.into()),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn walking_timeline_periods() {
+        let date = NaiveDate::from_ymd_opt(2018, 12, 13).unwrap();
+
+        let week = Period::Week.start(date);
+        let month = Period::Month.start(date);
+
+        assert_eq!(Period::Day.start(date), date);
+        assert_eq!(week, NaiveDate::from_ymd_opt(2018, 12, 10).unwrap());
+        assert_eq!(month, NaiveDate::from_ymd_opt(2018, 12, 1).unwrap());
+
+        assert_eq!(Period::Week.label(week), "2018-W50");
+        assert_eq!(Period::Month.label(Period::Month.next(month)), "2019-01");
+    }
+
+    #[test]
+    fn reporting_shifted_timeline() {
+        let zone = dates::parse_zone("+02:00").unwrap();
+        let mut metric = Timeline::new(Period::Day, &Formatter::new(zone, None).unwrap());
+
+        metric.register(&object("2018-01-01T23:00:00.000Z"));
+        metric.register(&object("2018-01-02T12:00:00.000Z"));
+        metric.register(&object("2018-01-10T12:00:00.000Z"));
+
+        let mut report = Report::new();
+        metric.report(&mut report);
+
+        let get = |label| report.get("timeline", label);
+
+        assert_eq!(get("2018-01-01_files"), None);
+        assert_eq!(get("2018-01-02_files"), Some("2"));
+        assert_eq!(get("2018-01-05_files"), None);
+        assert_eq!(get("2018-01-10_files"), Some("1"));
+        assert_eq!(get("timeline_empty_periods"), Some("7"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Options module for the runtime configuration of a scan.
+use chrono::{DateTime, Utc};
+use config::Group;
+use dates::Formatter;
+use lifecycle;
+use metrics::timeline::Period;
+use source::Source;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Runtime options used to configure a scan.
+///
+/// All fields are public, so options can be created using `Options::new`
+/// and then customized before being provided to a `Scanner`.
+pub struct Options {
+    /// The name of the bucket to gather metadata about.
+    pub bucket: String,
+    /// An optional prefix used to filter a subsection of the bucket.
+    pub prefix: Option<String>,
+    /// The time to use as "now" when calculating object ages.
+    pub reference: DateTime<Utc>,
+    /// The formatter to use when writing timestamps to the output.
+    pub formatter: Formatter,
+    /// An optional period to bucket an upload timeline into.
+    pub timeline: Option<Period>,
+    /// Whether to fetch and report on object owners.
+    pub owners: bool,
+    /// Whether to report on the lengths and depths of keys.
+    pub keys: bool,
+    /// Whether to audit key names for problematic characters.
+    pub hygiene: bool,
+    /// Whether to report on zero-byte objects and folder markers.
+    pub markers: bool,
+    /// Whether to detect and report on Hive-style partitions.
+    pub partitions: bool,
+    /// Whether to detect and report on date partitioned layouts.
+    pub date_partitions: bool,
+    /// Whether to detect and report on colliding keys.
+    pub collisions: bool,
+    /// Whether to analyse the distribution of keys across prefixes.
+    pub hot_spots: bool,
+    /// An optional size threshold to report small files below.
+    pub small_files: Option<u64>,
+    /// Whether to inspect object headers via `HeadObject`.
+    pub deep: bool,
+    /// Whether to inspect object tags via `GetObjectTagging`.
+    pub tags: bool,
+    /// The number of concurrent requests to use when inspecting objects.
+    pub concurrency: usize,
+    /// An optional limit on requests per second when inspecting objects.
+    pub rate_limit: Option<u32>,
+    /// An optional source of lifecycle rules to simulate.
+    pub lifecycle: Option<lifecycle::Source>,
+    /// An optional fraction of prefixes to sample, rather than a full scan.
+    pub sample: Option<f64>,
+    /// An optional number of random pages to sample, rather than a full scan.
+    pub sample_pages: Option<u64>,
+    /// An optional limit on the number of listing requests to make.
+    pub max_requests: Option<u64>,
+    /// An optional limit on the duration of a scan.
+    pub max_time: Option<Duration>,
+    /// The source to enumerate objects from.
+    pub source: Source,
+    /// An optional file to save all objects seen during a scan to.
+    pub dump_listing: Option<PathBuf>,
+    /// An optional database to append the results of the scan to.
+    pub history: Option<PathBuf>,
+    /// An optional set of metric sections to restrict a scan to.
+    pub metrics: Option<Vec<String>>,
+    /// A set of metric sections to skip during a scan.
+    pub skip_metrics: Vec<String>,
+    /// A set of custom groups to report on, defined by configuration.
+    pub groups: Vec<Group>,
+}
+
+/// Main implementation.
+impl Options {
+    /// Constructs new `Options` to scan a bucket and optional prefix.
+    ///
+    /// All other options are set to their defaults, which results in a
+    /// full listing of the target using the default chain of metrics.
+    pub fn new(bucket: &str, prefix: Option<&str>) -> Options {
+        Options {
+            bucket: bucket.to_string(),
+            prefix: prefix.map(|prefix| prefix.to_string()),
+            reference: Utc::now(),
+            formatter: Formatter::default(),
+            timeline: None,
+            owners: false,
+            keys: false,
+            hygiene: false,
+            markers: false,
+            partitions: false,
+            date_partitions: false,
+            collisions: false,
+            hot_spots: false,
+            small_files: None,
+            deep: false,
+            tags: false,
+            concurrency: 16,
+            rate_limit: None,
+            lifecycle: None,
+            sample: None,
+            sample_pages: None,
+            max_requests: None,
+            max_time: None,
+            source: Source::Bucket,
+            dump_listing: None,
+            history: None,
+            metrics: None,
+            skip_metrics: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Determines whether a metric section is selected for a scan.
+    ///
+    /// Sections are selected unless they're skipped, or a set of sections
+    /// is provided which doesn't contain them.
+    pub fn selects(&self, section: &str) -> bool {
+        let included = match self.metrics {
+            Some(ref metrics) => metrics.iter().any(|name| name == section),
+            None => true,
+        };
+        included && !self.skip_metrics.iter().any(|name| name == section)
+    }
+
+    /// Retrieves the bucket and prefix used to key runs in a history database.
+    ///
+    /// Local directories are keyed by their path, without any prefix.
+    pub fn history_key(&self) -> (String, String) {
+        match self.source {
+            Source::Directory(ref path) => (path.to_string_lossy().into_owned(), String::new()),
+            _ => (self.bucket.clone(), self.prefix.clone().unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Options;
+
+    #[test]
+    fn selecting_metric_sections() {
+        let mut options = Options::new("my.bucket", None);
+
+        assert!(options.selects("general"));
+
+        options.metrics = Some(vec!["general".into(), "age".into()]);
+        options.skip_metrics = vec!["age".into()];
+
+        assert!(options.selects("general"));
+        assert!(!options.selects("age"));
+        assert!(!options.selects("extensions"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Report module to collect the structured output of a scan.
+//!
+//! Metrics write their statistics into a `Report` as sections of labelled
+//! values, which can then be printed or stored. Printing a report gives the
+//! familiar `[section]` and `label=value` format.
+use bounded::Bounded;
+use std::fmt::{self, Display, Formatter};
+
+/// Report structure containing sections of labelled values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    sections: Vec<Section>,
+}
+
+/// Section structure containing a named group of labelled values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Section {
+    /// The name of the section, e.g. `general`.
+    pub name: String,
+    /// The labelled values of the section, in insertion order.
+    pub pairs: Vec<(String, String)>,
+}
+
+/// Main implementation.
+impl Report {
+    /// Constructs a new, empty `Report`.
+    pub fn new() -> Report {
+        Report::default()
+    }
+
+    /// Begins a new section with the provided name.
+    pub fn head(&mut self, label: &str) {
+        self.sections.push(Section {
+            name: label.to_string(),
+            pairs: Vec::new(),
+        });
+    }
+
+    /// Adds a label/value pair to the current section.
+    pub fn pair<T>(&mut self, label: &str, val: T)
+    where
+        T: Display,
+    {
+        self.sections
+            .last_mut()
+            .expect("pairs should be added to a section")
+            .pairs
+            .push((label.to_string(), val.to_string()));
+    }
+
+    /// Adds a bounded value, conditionally based on content.
+    pub fn bound<L, T>(&mut self, label: &str, bounded: &Bounded<T>, logger: L)
+    where
+        L: FnOnce(&mut Report, T),
+        T: Clone,
+    {
+        let bounded_key = bounded.key().clone();
+
+        if bounded_key.is_none() {
+            return;
+        }
+
+        let bounded_val = bounded.value().clone();
+        let bounded_cnt = bounded.count();
+
+        let key = bounded_key.unwrap();
+
+        logger(self, bounded_val);
+        self.pair(&format!("{}_name", label), key);
+
+        if bounded_cnt > 1 {
+            self.pair(&format!("{}_others", label), bounded_cnt);
+        }
+    }
+
+    /// Adds a label/value pair to the start of every existing section.
+    pub fn annotate<T>(&mut self, label: &str, val: T)
+    where
+        T: Display,
+    {
+        let val = val.to_string();
+
+        for section in &mut self.sections {
+            section.pairs.insert(0, (label.to_string(), val.clone()));
+        }
+    }
+
+    /// Retrieves all sections of the report.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Retrieves a value from a section of the report by label.
+    pub fn get(&self, section: &str, label: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .filter(|s| s.name == section)
+            .flat_map(|s| s.pairs.iter())
+            .find(|(l, _)| l == label)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Display implementation.
+impl Display for Report {
+    /// Formats the report using the `[section]` and `label=value` format.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (idx, section) in self.sections.iter().enumerate() {
+            // sections are separated by an empty line
+            if idx > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "[{}]", section.name)?;
+
+            for (label, val) in &section.pairs {
+                writeln!(f, "{}={}", label, val)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Report;
+
+    #[test]
+    fn formatting_reports() {
+        let mut report = Report::new();
+
+        report.head("general");
+        report.pair("total_files", 3);
+        report.head("extensions");
+        report.pair("most_popular_extension", "txt");
+
+        assert_eq!(report.get("general", "total_files"), Some("3"));
+        assert_eq!(report.get("general", "unknown"), None);
+        assert_eq!(
+            report.to_string(),
+            "[general]\ntotal_files=3\n\n[extensions]\nmost_popular_extension=txt\n"
+        );
+    }
+
+    #[test]
+    fn annotating_reports() {
+        let mut report = Report::new();
+
+        report.head("general");
+        report.pair("total_files", 3);
+        report.annotate("estimated", true);
+
+        assert_eq!(
+            report.to_string(),
+            "[general]\nestimated=true\ntotal_files=3\n"
+        );
+    }
+}
@@ -0,0 +1,496 @@
+//! Sampling module to bound the work done when scanning huge buckets.
+//!
+//! Sampling picks a random subset of the prefixes beneath the target prefix,
+//! scans them in full, and extrapolates totals across all prefixes (with a
+//! 95% confidence interval). Prefixes are discovered a level deeper while
+//! there are too few to sample from. Flat key spaces can instead be sampled
+//! by listing pages at random points in the key space. Scans can also be
+//! bounded by a number of requests or a duration, in which case output is
+//! partial.
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use report::Report;
+use rusoto_s3::Object;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// The z-score used to calculate a 95% confidence interval.
+const Z_SCORE: f64 = 1.96;
+
+/// The minimum number of prefixes to sample from before descending.
+const MIN_PREFIXES: usize = 10;
+
+/// The maximum depth to discover prefixes beneath the base.
+const MAX_DEPTH: usize = 3;
+
+/// The number of characters of a key used to place it in the key space.
+const DIGITS: u32 = 19;
+
+/// The range of (printable ASCII) characters used to place keys.
+const RADIX: u128 = 96;
+
+/// The size of the key space that keys are placed in.
+const SPACE: u128 = RADIX.pow(DIGITS);
+
+/// An estimate alongside the lower and upper bounds of its interval.
+type Interval = (f64, f64, f64);
+
+/// Budget structure to limit the requests made during a scan.
+pub struct Budget {
+    requests: u64,
+    max_requests: Option<u64>,
+    deadline: Option<Instant>,
+    exhausted: Option<&'static str>,
+}
+
+/// Main implementation.
+impl Budget {
+    /// Constructs a new `Budget` from optional request and time limits.
+    pub fn new(max_requests: Option<u64>, max_time: Option<Duration>) -> Budget {
+        Budget {
+            requests: 0,
+            max_requests,
+            deadline: max_time.map(|time| Instant::now() + time),
+            exhausted: None,
+        }
+    }
+
+    /// Consumes a request from the budget, if there is any remaining.
+    pub fn consume(&mut self) -> bool {
+        if self.exhausted.is_some() {
+            return false;
+        }
+
+        if self.max_requests.is_some_and(|max| self.requests >= max) {
+            self.exhausted = Some("request_budget");
+            return false;
+        }
+
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.exhausted = Some("time_budget");
+            return false;
+        }
+
+        self.requests += 1;
+        true
+    }
+
+    /// Determines whether this budget has any limits at all.
+    pub fn is_limited(&self) -> bool {
+        self.max_requests.is_some() || self.deadline.is_some()
+    }
+}
+
+/// Sampler structure to track and extrapolate a sample of prefixes.
+pub struct Sampler {
+    base: String,
+    depth: usize,
+    prefixes: Vec<String>,
+    completed: HashSet<String>,
+    strata: HashMap<String, (u64, u64)>,
+    root: (u64, u64),
+}
+
+/// Main implementation.
+impl Sampler {
+    /// Constructs a new `Sampler` for prefixes beneath a base.
+    pub fn new(base: &str) -> Sampler {
+        Sampler {
+            base: base.to_string(),
+            depth: 1,
+            prefixes: Vec::new(),
+            completed: HashSet::new(),
+            strata: HashMap::new(),
+            root: (0, 0),
+        }
+    }
+
+    /// Adds a discovered prefix to the sampling population.
+    pub fn add_prefix(&mut self, prefix: String) {
+        self.prefixes.push(prefix);
+    }
+
+    /// Determines whether there are too few prefixes to sample from.
+    pub fn should_descend(&self) -> bool {
+        !self.prefixes.is_empty() && self.prefixes.len() < MIN_PREFIXES && self.depth < MAX_DEPTH
+    }
+
+    /// Moves the sampling population a level deeper.
+    ///
+    /// The current prefixes are returned to discover the next level beneath,
+    /// and any objects directly beneath them are counted without sampling.
+    pub fn descend(&mut self) -> Vec<String> {
+        self.depth += 1;
+        std::mem::take(&mut self.prefixes)
+    }
+
+    /// Selects a random subset of the discovered prefixes.
+    ///
+    /// At least a single prefix is selected, as long as any exist.
+    pub fn select(&mut self, fraction: f64) -> Vec<String> {
+        let count = (self.prefixes.len() as f64 * fraction).ceil() as usize;
+        let mut prefixes = self.prefixes.clone();
+
+        prefixes.shuffle(&mut thread_rng());
+        prefixes.truncate(count.max(1));
+        prefixes
+    }
+
+    /// Marks a sampled prefix as being scanned in full.
+    pub fn complete(&mut self, prefix: &str) {
+        self.completed.insert(prefix.to_string());
+    }
+
+    /// Registers an object with the sample.
+    pub fn register(&mut self, object: &Object) {
+        let key = object.key.as_ref().map_or("", |key| key.as_str());
+        let size = object.size.unwrap_or(0) as u64;
+
+        // objects above the sampled depth live at the root
+        let rest = key.get(self.base.len()..).unwrap_or("");
+        let entry = match rest.match_indices('/').nth(self.depth - 1) {
+            None => &mut self.root,
+            Some((idx, _)) => {
+                let prefix = &key[..self.base.len() + idx + 1];
+                self.strata.entry(prefix.to_string()).or_insert((0, 0))
+            }
+        };
+
+        entry.0 += 1;
+        entry.1 += size;
+    }
+
+    /// Estimates totals of files and bytes across the full population.
+    ///
+    /// Each estimate is returned with the bounds of its confidence interval,
+    /// or `None` if no prefixes were completed (so nothing can be estimated).
+    fn estimate(&self) -> Option<(Interval, Interval)> {
+        if self.completed.is_empty() && !self.prefixes.is_empty() {
+            return None;
+        }
+
+        let totals: Vec<(u64, u64)> = self
+            .completed
+            .iter()
+            .map(|prefix| self.strata.get(prefix).cloned().unwrap_or((0, 0)))
+            .collect();
+
+        let population = self.prefixes.len() as f64;
+        let files = extrapolate(totals.iter().map(|t| t.0 as f64), population);
+        let bytes = extrapolate(totals.iter().map(|t| t.1 as f64), population);
+
+        let shift = |(est, low, high): Interval, root: u64| {
+            let root = root as f64;
+            (est + root, (low + root).max(root), high + root)
+        };
+
+        Some((shift(files, self.root.0), shift(bytes, self.root.1)))
+    }
+}
+
+/// Page sampler structure to track and extrapolate a sample of pages.
+///
+/// Keys are placed in a key space of `[0, 1)` by their leading characters,
+/// and pages are listed from random points in that space. Each page gives
+/// the density of keys (and bytes) between its starting point and its last
+/// key, and the average density is an estimate of the total.
+pub struct Pages {
+    prefix: String,
+    requested: u64,
+    densities: Vec<(f64, f64)>,
+}
+
+/// Main implementation.
+impl Pages {
+    /// Constructs a new `Pages` sampler for keys beneath a prefix.
+    pub fn new(prefix: &str, requested: u64) -> Pages {
+        Pages {
+            prefix: prefix.to_string(),
+            requested,
+            densities: Vec::new(),
+        }
+    }
+
+    /// Picks a random point in the key space, returned as a key to list after.
+    pub fn pick(&self) -> String {
+        let point = thread_rng().gen_range(0..SPACE);
+        let mut key = self.prefix.clone();
+
+        for idx in (0..DIGITS).rev() {
+            let digit = (point / RADIX.pow(idx)) % RADIX;
+            key.push(char::from(0x20 + digit as u8));
+        }
+
+        key
+    }
+
+    /// Registers a page listed after a key with the sample.
+    ///
+    /// Pages which reach the end of the listing cover the rest of the space.
+    pub fn register(&mut self, start: &str, contents: &[Object], truncated: bool) {
+        let start = self.position(start);
+        let end = match contents.last() {
+            Some(object) if truncated => self.position(object.key.as_deref().unwrap_or("")),
+            _ => SPACE,
+        };
+
+        // the share of the key space covered by the page
+        let width = end.saturating_sub(start).max(1) as f64 / SPACE as f64;
+
+        let files = contents.len() as f64;
+        let bytes = contents
+            .iter()
+            .map(|object| object.size.unwrap_or(0) as f64)
+            .sum::<f64>();
+
+        self.densities.push((files / width, bytes / width));
+    }
+
+    /// Places a key within the key space, as an integer of `DIGITS` digits.
+    fn position(&self, key: &str) -> u128 {
+        let rest = key.get(self.prefix.len()..).unwrap_or("").as_bytes();
+
+        (0..DIGITS as usize).fold(0, |position, idx| {
+            // characters outside of printable ASCII are clamped to each end
+            let digit = rest
+                .get(idx)
+                .map_or(0, |byte| u128::from(byte.clamp(&0x20, &0x7F) - 0x20));
+
+            position * RADIX + digit
+        })
+    }
+
+    /// Estimates totals of files and bytes across the full key space.
+    fn estimate(&self) -> Option<(Interval, Interval)> {
+        if self.densities.is_empty() {
+            return None;
+        }
+
+        let files = average(self.densities.iter().map(|d| d.0));
+        let bytes = average(self.densities.iter().map(|d| d.1));
+
+        Some((files, bytes))
+    }
+}
+
+/// Determines the base prefix to discover sampling prefixes beneath.
+pub fn base(prefix: &Option<String>) -> String {
+    match prefix {
+        Some(ref prefix) if !prefix.is_empty() && !prefix.ends_with('/') => format!("{}/", prefix),
+        Some(ref prefix) => prefix.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Reports the sampling state under the `sampling` header.
+pub fn report(
+    report: &mut Report,
+    sampler: Option<&Sampler>,
+    pages: Option<&Pages>,
+    budget: &Budget,
+) {
+    // next segment: sampling
+    report.head("sampling");
+
+    // log out whether the output is estimated or partial
+    report.pair("estimated", sampler.is_some() || pages.is_some());
+    report.pair("complete", budget.exhausted.is_none());
+
+    if let Some(reason) = budget.exhausted {
+        report.pair("stop_reason", reason);
+    }
+
+    report.pair("total_requests", budget.requests);
+
+    // page sampling specific statistics
+    if let Some(pages) = pages {
+        report.pair("requested_pages", pages.requested);
+        report.pair("sampled_pages", pages.densities.len());
+
+        if let Some(estimate) = pages.estimate() {
+            report_estimate(report, estimate);
+        }
+    }
+
+    // prefix sampling specific statistics
+    if let Some(sampler) = sampler {
+        report.pair("sampled_depth", sampler.depth);
+        report.pair("total_prefixes", sampler.prefixes.len());
+        report.pair("sampled_prefixes", sampler.completed.len());
+
+        if let Some(estimate) = sampler.estimate() {
+            report_estimate(report, estimate);
+        }
+    }
+}
+
+/// Reports estimated totals of files and bytes, with their intervals.
+fn report_estimate(report: &mut Report, (files, bytes): (Interval, Interval)) {
+    report.pair("estimated_total_files", files.0.round());
+    report.pair("estimated_total_files_low", files.1.round());
+    report.pair("estimated_total_files_high", files.2.round());
+    report.pair(
+        "estimated_total_storage",
+        ::util::convert_bytes(bytes.0.round() as u64),
+    );
+    report.pair("estimated_total_bytes", bytes.0.round());
+    report.pair("estimated_total_bytes_low", bytes.1.round());
+    report.pair("estimated_total_bytes_high", bytes.2.round());
+}
+
+/// Extrapolates a population total from a sample of cluster totals.
+///
+/// Returns the estimate alongside the lower and upper bounds of the
+/// confidence interval, using a finite population correction.
+fn extrapolate<I>(samples: I, population: f64) -> Interval
+where
+    I: Iterator<Item = f64>,
+{
+    let samples: Vec<f64> = samples.collect();
+    let count = samples.len() as f64;
+
+    if samples.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+
+    let mean = samples.iter().sum::<f64>() / count;
+    let variance = if samples.len() > 1 {
+        samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.0)
+    } else {
+        0.0
+    };
+
+    let estimate = population * mean;
+    let correction = 1.0 - count / population;
+    let error = Z_SCORE * (population.powi(2) * correction * variance / count).sqrt();
+
+    (estimate, (estimate - error).max(0.0), estimate + error)
+}
+
+/// Averages a sample drawn from an unbounded population.
+///
+/// Returns the average alongside the lower and upper bounds of the
+/// confidence interval, without any finite population correction.
+fn average<I>(samples: I) -> Interval
+where
+    I: Iterator<Item = f64>,
+{
+    let samples: Vec<f64> = samples.collect();
+    let count = samples.len() as f64;
+
+    if samples.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+
+    let mean = samples.iter().sum::<f64>() / count;
+    let variance = if samples.len() > 1 {
+        samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.0)
+    } else {
+        0.0
+    };
+
+    let error = Z_SCORE * (variance / count).sqrt();
+
+    (mean, (mean - error).max(0.0), mean + error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, Pages, Sampler};
+    use rusoto_s3::Object;
+
+    fn object(key: &str, size: i64) -> Object {
+        Object {
+            key: Some(key.into()),
+            size: Some(size),
+            ..Object::default()
+        }
+    }
+
+    #[test]
+    fn extrapolating_samples() {
+        let (est, low, high) = super::extrapolate(vec![10.0, 20.0, 30.0].into_iter(), 30.0);
+
+        assert_eq!(est, 600.0);
+        assert!(low < est && high > est);
+        assert!(((est - low) - (high - est)).abs() < 1e-6);
+
+        let (est, low, high) = super::extrapolate(vec![10.0, 20.0].into_iter(), 2.0);
+
+        assert_eq!((est, low, high), (30.0, 30.0, 30.0));
+    }
+
+    #[test]
+    fn consuming_request_budgets() {
+        let mut budget = Budget::new(Some(2), None);
+
+        assert!(budget.is_limited());
+        assert!(budget.consume());
+        assert!(budget.consume());
+        assert!(!budget.consume());
+        assert_eq!(budget.exhausted, Some("request_budget"));
+        assert!(!Budget::new(None, None).is_limited());
+    }
+
+    #[test]
+    fn determining_sample_bases() {
+        assert_eq!(super::base(&None), "");
+        assert_eq!(super::base(&Some("logs".into())), "logs/");
+        assert_eq!(super::base(&Some("logs/".into())), "logs/");
+    }
+
+    #[test]
+    fn descending_sample_prefixes() {
+        let mut sampler = Sampler::new("logs/");
+
+        sampler.add_prefix("logs/2018/".into());
+        sampler.register(&object("logs/root.txt", 1));
+
+        assert!(sampler.should_descend());
+        assert_eq!(sampler.descend(), vec!["logs/2018/".to_string()]);
+        assert!(!sampler.should_descend());
+
+        sampler.add_prefix("logs/2018/01/".into());
+        sampler.register(&object("logs/2018/file.txt", 2));
+        sampler.register(&object("logs/2018/01/file.txt", 4));
+        sampler.complete("logs/2018/01/");
+
+        assert_eq!(sampler.root, (2, 3));
+        assert_eq!(sampler.strata.get("logs/2018/01/"), Some(&(1, 4)));
+    }
+
+    #[test]
+    fn estimating_from_pages() {
+        let mut pages = Pages::new("logs/", 2);
+
+        // points are always placed within the prefix
+        assert!(pages.pick().starts_with("logs/"));
+        assert_eq!(pages.position("logs/"), 0);
+        assert!(pages.position("logs/a") < pages.position("logs/b"));
+
+        // a page covering the whole key space counts exactly
+        pages.register(
+            "logs/",
+            &[object("logs/a", 10), object("logs/b", 20)],
+            false,
+        );
+
+        let ((files, _, _), (bytes, _, _)) = pages.estimate().unwrap();
+
+        assert!((files - 2.0).abs() < 1e-6);
+        assert!((bytes - 30.0).abs() < 1e-6);
+
+        // a page covering half of the key space counts twice
+        let mut pages = Pages::new("", 1);
+        let half = (0x20 + 48u8) as char;
+
+        pages.register(&half.to_string(), &[object("z", 1)], false);
+
+        let ((files, _, _), _) = pages.estimate().unwrap();
+
+        assert!((files - 2.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,381 @@
+//! Scanning module to walk object sources and feed metrics.
+use deep::Fetcher;
+use listing::Writer;
+use metrics::{self, Metric};
+use options::Options;
+use report::Report;
+use rusoto_s3::{ListObjectsV2Output, ListObjectsV2Request, Object, S3Client, S3};
+use sample::{Budget, Pages, Sampler};
+use source::Source;
+use types::MetaResult;
+
+/// Scanner structure to list objects and feed them through metrics.
+///
+/// The scanner owns the chain of metrics, alongside any state required
+/// to inspect objects further or to limit the work done during a scan.
+pub struct Scanner<'a> {
+    s3: &'a S3Client,
+    options: &'a Options,
+    fetcher: Fetcher<'a>,
+    budget: Budget,
+    sampler: Option<Sampler>,
+    pages: Option<Pages>,
+    pending: Option<Vec<Object>>,
+    dump: Option<Writer>,
+    chain: Vec<Box<dyn Metric>>,
+}
+
+/// Main implementation.
+impl<'a> Scanner<'a> {
+    /// Constructs a new `Scanner` from a client and options.
+    ///
+    /// The chain of metrics is built from the provided options.
+    pub fn new(s3: &'a S3Client, options: &'a Options) -> Scanner<'a> {
+        Scanner::with_chain(s3, options, metrics::chain(options))
+    }
+
+    /// Constructs a new `Scanner` using a custom chain of metrics.
+    pub fn with_chain(
+        s3: &'a S3Client,
+        options: &'a Options,
+        chain: Vec<Box<dyn Metric>>,
+    ) -> Scanner<'a> {
+        Scanner {
+            s3,
+            options,
+            fetcher: Fetcher::new(s3, &options.bucket, options.concurrency, options.rate_limit),
+            budget: Budget::new(options.max_requests, options.max_time),
+            sampler: None,
+            pages: None,
+            pending: None,
+            dump: None,
+            chain,
+        }
+    }
+
+    /// Runs the scan, saving the listing of objects when requested.
+    pub fn run(&mut self) -> MetaResult<()> {
+        // create the listing file before the scan begins
+        if let Some(ref path) = self.options.dump_listing {
+            self.dump = Some(Writer::create(path)?);
+        }
+
+        self.enumerate()?;
+
+        // flush the listing file, reporting any failures
+        if let Some(ref mut dump) = self.dump {
+            dump.finish()?;
+        }
+
+        Ok(())
+    }
+
+    /// Enumerates all objects from the source, based on the options.
+    fn enumerate(&mut self) -> MetaResult<()> {
+        // inventory reports and local directories replace the listing
+        let (s3, options) = (self.s3, self.options);
+        match options.source {
+            Source::Bucket => (),
+            Source::Inventory(ref location) => {
+                return ::inventory::read(
+                    s3,
+                    location,
+                    &options.bucket,
+                    options.prefix.as_ref(),
+                    |page, records| {
+                        self.process(page);
+
+                        // feed through the inventory-only columns
+                        for (entry, record) in page.iter().zip(records) {
+                            for metric in &mut self.chain {
+                                metric.register_inventory(entry, record);
+                            }
+                        }
+                    },
+                );
+            }
+            Source::Listing(ref path) => {
+                return ::listing::read(path, options.prefix.as_ref(), |page| self.process(page));
+            }
+            Source::Directory(ref root) => {
+                return ::source::walk(root, |page| self.process(page));
+            }
+        }
+
+        // page sampled scans list pages from random points
+        if let Some(pages) = self.options.sample_pages {
+            return self.sample_pages(pages);
+        }
+
+        // full scans only need to walk the target prefix
+        let fraction = match self.options.sample {
+            Some(fraction) => fraction,
+            None => {
+                let prefix = self.options.prefix.clone();
+                return self.scan(prefix).map(|_| ());
+            }
+        };
+
+        // sampled scans begin by discovering the prefixes to sample
+        let base = ::sample::base(&self.options.prefix);
+
+        self.sampler = Some(Sampler::new(&base));
+
+        if !self.discover(&base)? {
+            return Ok(());
+        }
+
+        // discover deeper prefixes while there are too few to sample
+        while self.sampler_mut().should_descend() {
+            for prefix in self.sampler_mut().descend() {
+                if !self.discover(&prefix)? {
+                    return Ok(());
+                }
+            }
+        }
+
+        // pick a random subset of the discovered prefixes
+        let prefixes = self.sampler_mut().select(fraction);
+
+        // walk each of the sampled prefixes, until the budget runs out
+        for prefix in prefixes {
+            // hold back objects until the prefix is complete, so that
+            // partially scanned prefixes never feed into the metrics
+            self.pending = Some(Vec::new());
+
+            let complete = self.scan(Some(prefix.clone()))?;
+            let pending = self.pending.take().unwrap_or_default();
+
+            if !complete {
+                break;
+            }
+
+            self.process(&pending);
+            self.sampler_mut().complete(&prefix);
+        }
+
+        Ok(())
+    }
+
+    /// Samples a number of pages from random points beneath the prefix.
+    fn sample_pages(&mut self, count: u64) -> MetaResult<()> {
+        let prefix = self.options.prefix.clone().unwrap_or_default();
+
+        self.pages = Some(Pages::new(&prefix, count));
+
+        for _ in 0..count {
+            let start = self.pages_mut().pick();
+
+            // create a request to list a page after the point
+            let request = ListObjectsV2Request {
+                bucket: self.options.bucket.clone(),
+                prefix: Some(prefix.clone()),
+                start_after: Some(start.clone()),
+                fetch_owner: Some(self.options.owners && self.options.selects("owners")),
+                ..ListObjectsV2Request::default()
+            };
+
+            // execute the request, unless we've run out of budget
+            let response = match self.list(request)? {
+                Some(response) => response,
+                None => break,
+            };
+
+            let contents = response.contents.unwrap_or_default();
+            let truncated = response.is_truncated.unwrap_or(false);
+
+            self.pages_mut().register(&start, &contents, truncated);
+            self.process(&contents);
+        }
+
+        Ok(())
+    }
+
+    /// Discovers the prefixes directly beneath a base prefix.
+    ///
+    /// Any objects found directly beneath the base are processed as they
+    /// are found, so they're included in the sample without extrapolation.
+    fn discover(&mut self, base: &str) -> MetaResult<bool> {
+        // iteration token
+        let mut token = None;
+
+        loop {
+            // create a request to list prefixes
+            let request = ListObjectsV2Request {
+                bucket: self.options.bucket.clone(),
+                prefix: Some(base.to_string()),
+                delimiter: Some("/".into()),
+                continuation_token: token,
+                fetch_owner: Some(self.options.owners && self.options.selects("owners")),
+                ..ListObjectsV2Request::default()
+            };
+
+            // execute the request, unless we've run out of budget
+            let response = match self.list(request)? {
+                Some(response) => response,
+                None => return Ok(false),
+            };
+
+            // process objects at the root of the base
+            if let Some(contents) = response.contents {
+                self.process(&contents);
+            }
+
+            // store all prefixes for sampling
+            for prefix in response.common_prefixes.unwrap_or_default() {
+                if let Some(prefix) = prefix.prefix {
+                    self.sampler_mut().add_prefix(prefix);
+                }
+            }
+
+            // break if there's no way to continue
+            if response.next_continuation_token.is_none() {
+                return Ok(true);
+            }
+
+            // store the token for next iteration
+            token = response.next_continuation_token;
+        }
+    }
+
+    /// Scans all objects beneath a prefix.
+    ///
+    /// Returns `false` if the budget was exhausted before the scan completed.
+    pub fn scan(&mut self, prefix: Option<String>) -> MetaResult<bool> {
+        // iteration token
+        let mut token = None;
+
+        loop {
+            // create a request to list objects
+            let request = ListObjectsV2Request {
+                bucket: self.options.bucket.clone(),
+                prefix: prefix.clone(),
+                continuation_token: token,
+                fetch_owner: Some(self.options.owners && self.options.selects("owners")),
+                ..ListObjectsV2Request::default()
+            };
+
+            // execute the request, unless we've run out of budget
+            let response = match self.list(request)? {
+                Some(response) => response,
+                None => return Ok(false),
+            };
+
+            // check contents (although should always be there)
+            if let Some(contents) = response.contents {
+                match self.pending {
+                    Some(ref mut pending) => pending.extend(contents),
+                    None => self.process(&contents),
+                }
+            }
+
+            // break if there's no way to continue
+            if response.next_continuation_token.is_none() {
+                return Ok(true);
+            }
+
+            // store the token for next iteration
+            token = response.next_continuation_token;
+        }
+    }
+
+    /// Executes a listing request, as long as there is budget remaining.
+    pub fn list(
+        &mut self,
+        request: ListObjectsV2Request,
+    ) -> MetaResult<Option<ListObjectsV2Output>> {
+        if !self.budget.consume() {
+            return Ok(None);
+        }
+
+        // execute the request and await the response (blocking)
+        Ok(Some(self.s3.list_objects_v2(request).sync()?))
+    }
+
+    /// Processes a page of objects through all metrics.
+    fn process(&mut self, contents: &[Object]) {
+        // iterate all objects
+        for entry in contents {
+            // iterate all metrics meters
+            for metric in &mut self.chain {
+                metric.register(entry);
+            }
+
+            // track the object in the sample
+            if let Some(ref mut sampler) = self.sampler {
+                sampler.register(entry);
+            }
+        }
+
+        // save the objects to the listing, if requested
+        if let Some(ref mut dump) = self.dump {
+            dump.write(contents);
+        }
+
+        // inspect object headers in deep mode, if any metric needs them
+        let heads = self.options.selects("content") || self.options.selects("encryption");
+
+        if self.options.deep && heads {
+            for (entry, head) in contents.iter().zip(self.fetcher.heads(contents)) {
+                // failures are reported by the metrics themselves
+                for metric in &mut self.chain {
+                    match head {
+                        Ok(ref head) => metric.register_head(entry, head),
+                        Err(_) => metric.register_failed_head(entry),
+                    }
+                }
+            }
+        }
+
+        // inspect object tags when requested, if the metric needs them
+        if self.options.tags && self.options.selects("tagging") {
+            for (entry, tags) in contents.iter().zip(self.fetcher.tags(contents)) {
+                // failures are reported by the metrics themselves
+                if let Ok(tags) = tags {
+                    for metric in &mut self.chain {
+                        metric.register_tags(entry, &tags);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Retrieves a mutable reference to the active sampler.
+    fn sampler_mut(&mut self) -> &mut Sampler {
+        self.sampler.as_mut().expect("sampler should be active")
+    }
+
+    /// Retrieves a mutable reference to the active page sampler.
+    fn pages_mut(&mut self) -> &mut Pages {
+        self.pages.as_mut().expect("page sampler should be active")
+    }
+
+    /// Creates a report containing the statistics of all metrics.
+    pub fn report(&self) -> Report {
+        let mut report = Report::new();
+
+        // report all statistics
+        for metric in &self.chain {
+            metric.report(&mut report);
+        }
+
+        // mark every section as estimated when sampling
+        let sampled = self.sampler.is_some() || self.pages.is_some();
+
+        if sampled {
+            report.annotate("estimated", true);
+        }
+
+        // note any sampling or incomplete scans
+        if sampled || self.budget.is_limited() {
+            ::sample::report(
+                &mut report,
+                self.sampler.as_ref(),
+                self.pages.as_ref(),
+                &self.budget,
+            );
+        }
+
+        report
+    }
+}
@@ -1,8 +1,8 @@
 //! Types module for the main runtime, exposing error and result types.
 use quick_xml::events::Event;
 use quick_xml::Reader;
-use rusoto_core::request;
-use rusoto_s3::ListObjectsV2Error;
+use rusoto_core::{request, CredentialsError, HttpDispatchError};
+use rusoto_s3::{GetObjectError, GetObjectTaggingError, HeadObjectError, ListObjectsV2Error};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::{io, time};
 
@@ -33,12 +33,12 @@ impl Display for MetaError {
 }
 
 /// Error translation from Rusoto to relay messages.
-impl From<ListObjectsV2Error> for MetaError {
-    /// Converts a `ListObjectsV2Error` to a `MetaError`.
-    fn from(err: ListObjectsV2Error) -> MetaError {
-        // grab the raw conversion
-        let msg = err.to_string();
-
+impl MetaError {
+    /// Converts a Rusoto error message to a `MetaError`.
+    ///
+    /// Rusoto errors often contain the raw XML body returned by S3, so
+    /// the inner message is extracted (when present) for readability.
+    pub(crate) fn from_rusoto(msg: String) -> MetaError {
         // XML, look for a message!
         if msg.starts_with("<?xml") {
             // create an XML reader and buffer
@@ -73,6 +73,23 @@ impl From<ListObjectsV2Error> for MetaError {
     }
 }
 
+/// Macro to implement `From` for Rusoto error types.
+macro_rules! derive_rusoto {
+    ($type:ty) => {
+        impl From<$type> for MetaError {
+            fn from(err: $type) -> MetaError {
+                MetaError::from_rusoto(err.to_string())
+            }
+        }
+    };
+}
+
+// Rusoto derivations of derive_rusoto.
+derive_rusoto!(GetObjectError);
+derive_rusoto!(GetObjectTaggingError);
+derive_rusoto!(HeadObjectError);
+derive_rusoto!(ListObjectsV2Error);
+
 /// Macro to implement `From` for provided types.
 macro_rules! derive_from {
     ($type:ty) => {
@@ -86,9 +103,14 @@ macro_rules! derive_from {
 
 // Easy derivations of derive_from.
 derive_from!(&'a str);
+derive_from!(CredentialsError);
+derive_from!(HttpDispatchError);
 derive_from!(io::Error);
+derive_from!(regex::Error);
 derive_from!(request::TlsError);
+derive_from!(rusqlite::Error);
 derive_from!(time::SystemTimeError);
+derive_from!(toml::de::Error);
 derive_from!(String);
 
 #[cfg(test)]
@@ -99,6 +121,7 @@ mod tests {
     use std::io::{Error, ErrorKind};
 
     #[test]
+    #[allow(clippy::io_other_error)]
     fn converting_io_to_error() {
         let message = "My fake access key failed message";
         let io_errs = Error::new(ErrorKind::Other, message);
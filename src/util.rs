@@ -1,51 +1,15 @@
 //! General utility module housing formatting functions.
-use bounded::Bounded;
 use pretty_bytes::converter::convert;
-use std::fmt::Display;
+
+/// The name used for keys at the root, which can't clash with a real
+/// prefix as prefixes always end with a `/`.
+pub const ROOT: &str = "<root>";
 
 /// Converts a byte count to a `String` representation.
 pub fn convert_bytes(bytes: u64) -> String {
     convert(bytes as f64).replacen(' ', "", 1)
 }
 
-/// Logs out a bounded value, conditionally based on content.
-pub fn log_bound<L, T>(label: &str, bounded: &Bounded<T>, logger: L)
-where
-    L: FnOnce(T),
-    T: Clone,
-{
-    let bounded_key = bounded.key().clone();
-
-    if bounded_key.is_none() {
-        return;
-    }
-
-    let bounded_val = bounded.value().clone();
-    let bounded_cnt = bounded.count();
-
-    let key = bounded_key.unwrap();
-
-    logger(bounded_val);
-    log_pair(&format!("{}_name", label), key);
-
-    if bounded_cnt > 1 {
-        log_pair(&format!("{}_others", label), bounded_cnt);
-    }
-}
-
-/// Logs a header using a common format.
-pub fn log_head(label: &str) {
-    println!("\n[{}]", label);
-}
-
-/// Logs a label/value pair using a common format.
-pub fn log_pair<T>(label: &str, val: T)
-where
-    T: Display,
-{
-    println!("{}={}", label, val);
-}
-
 #[cfg(test)]
 mod tests {
 